@@ -0,0 +1,78 @@
+use nalgebra_glm::{vec2, Vec2};
+
+use crate::engine2::verlet_object::VerletObject;
+
+/// Axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+impl Aabb {
+    #[must_use]
+    pub const fn new(min: Vec2, max: Vec2) -> Self {
+        Self { min, max }
+    }
+
+    /// Bounds of a disc.
+    #[must_use]
+    pub fn around(center: Vec2, radius: f32) -> Self {
+        let half_extents = vec2(radius, radius);
+        Self::new(center - half_extents, center + half_extents)
+    }
+
+    #[must_use]
+    pub fn of(obj: &VerletObject) -> Self {
+        Self::around(obj.get_center(), obj.get_radius())
+    }
+
+    /// Strict overlap test, boxes only touching each other don't overlap.
+    #[must_use]
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.min.x < other.max.x
+            && other.min.x < self.max.x
+            && self.min.y < other.max.y
+            && other.min.y < self.max.y
+    }
+
+    #[must_use]
+    pub fn contains(&self, other: &Self) -> bool {
+        self.min.x <= other.min.x
+            && self.min.y <= other.min.y
+            && other.max.x <= self.max.x
+            && other.max.y <= self.max.y
+    }
+
+    #[must_use]
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        self.min.x <= point.x
+            && point.x <= self.max.x
+            && self.min.y <= point.y
+            && point.y <= self.max.y
+    }
+
+    #[must_use]
+    pub fn merge(&self, other: &Self) -> Self {
+        Self::new(
+            vec2(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            vec2(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        )
+    }
+
+    #[must_use]
+    pub fn inflate(&self, margin: f32) -> Self {
+        let margin = vec2(margin, margin);
+        Self::new(self.min - margin, self.max + margin)
+    }
+
+    #[must_use]
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) / 2.0
+    }
+
+    #[must_use]
+    pub fn perimeter(&self) -> f32 {
+        let size = self.max - self.min;
+        2.0 * (size.x + size.y)
+    }
+}
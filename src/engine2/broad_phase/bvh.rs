@@ -0,0 +1,225 @@
+use crate::engine2::{aabb::Aabb, broad_phase::BroadPhase, verlet_object::VerletObject};
+
+const NULL: usize = usize::MAX;
+const DEFAULT_MARGIN: f32 = 0.005;
+
+struct Node {
+    aabb: Aabb,
+    parent: usize,
+    left: usize,
+    right: usize,
+    // index of the object for leaves, NULL for internal nodes
+    object: usize,
+}
+impl Node {
+    const fn is_leaf(&self) -> bool {
+        self.left == NULL
+    }
+}
+
+/// Dynamic AABB tree (bounding volume hierarchy).
+///
+/// Leaves store boxes enlarged by ``margin``, an object is only reinserted once it leaves
+/// its enlarged box, so slowly moving objects cost nothing to keep the tree up to date.
+/// The tree is rebuilt whenever the number of objects changes.
+pub struct DynamicAabbTree {
+    margin: f32,
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    root: usize,
+    leaf_of: Vec<usize>,
+    bounds: Vec<Aabb>,
+    stack: Vec<usize>,
+}
+impl DynamicAabbTree {
+    #[must_use]
+    pub const fn new(margin: f32) -> Self {
+        Self {
+            margin,
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: NULL,
+            leaf_of: Vec::new(),
+            bounds: Vec::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.leaf_of.clear();
+        self.root = NULL;
+    }
+
+    fn allocate(&mut self, aabb: Aabb, object: usize) -> usize {
+        let node = Node {
+            aabb,
+            parent: NULL,
+            left: NULL,
+            right: NULL,
+            object,
+        };
+        if let Some(idx) = self.free.pop() {
+            self.nodes[idx] = node;
+            idx
+        } else {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        }
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        if self.root == NULL {
+            self.root = leaf;
+            return;
+        }
+
+        // descend towards the cheapest sibling, cost being the perimeter growth
+        let leaf_aabb = self.nodes[leaf].aabb;
+        let mut sibling = self.root;
+        while !self.nodes[sibling].is_leaf() {
+            let node = &self.nodes[sibling];
+            let perimeter = node.aabb.perimeter();
+            let combined = node.aabb.merge(&leaf_aabb).perimeter();
+            let cost = 2.0 * combined;
+            let inheritance_cost = 2.0 * (combined - perimeter);
+
+            let child_cost = |child: usize| {
+                let child = &self.nodes[child];
+                let merged = leaf_aabb.merge(&child.aabb).perimeter();
+                if child.is_leaf() {
+                    merged + inheritance_cost
+                } else {
+                    merged - child.aabb.perimeter() + inheritance_cost
+                }
+            };
+            let left_cost = child_cost(node.left);
+            let right_cost = child_cost(node.right);
+
+            if cost < left_cost && cost < right_cost {
+                break;
+            }
+            sibling = if left_cost < right_cost {
+                node.left
+            } else {
+                node.right
+            };
+        }
+
+        let old_parent = self.nodes[sibling].parent;
+        let merged = self.nodes[sibling].aabb.merge(&leaf_aabb);
+        let new_parent = self.allocate(merged, NULL);
+        self.nodes[new_parent].parent = old_parent;
+        self.nodes[new_parent].left = sibling;
+        self.nodes[new_parent].right = leaf;
+        self.nodes[sibling].parent = new_parent;
+        self.nodes[leaf].parent = new_parent;
+
+        if old_parent == NULL {
+            self.root = new_parent;
+        } else if self.nodes[old_parent].left == sibling {
+            self.nodes[old_parent].left = new_parent;
+        } else {
+            self.nodes[old_parent].right = new_parent;
+        }
+
+        self.refit(old_parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        if leaf == self.root {
+            self.root = NULL;
+            return;
+        }
+
+        let parent = self.nodes[leaf].parent;
+        let grand_parent = self.nodes[parent].parent;
+        let sibling = if self.nodes[parent].left == leaf {
+            self.nodes[parent].right
+        } else {
+            self.nodes[parent].left
+        };
+
+        self.nodes[sibling].parent = grand_parent;
+        if grand_parent == NULL {
+            self.root = sibling;
+        } else {
+            if self.nodes[grand_parent].left == parent {
+                self.nodes[grand_parent].left = sibling;
+            } else {
+                self.nodes[grand_parent].right = sibling;
+            }
+            self.refit(grand_parent);
+        }
+        self.free.push(parent);
+    }
+
+    fn refit(&mut self, mut idx: usize) {
+        while idx != NULL {
+            let node = &self.nodes[idx];
+            self.nodes[idx].aabb = self.nodes[node.left]
+                .aabb
+                .merge(&self.nodes[node.right].aabb);
+            idx = self.nodes[idx].parent;
+        }
+    }
+
+    fn update(&mut self, objects: &[VerletObject]) {
+        if self.leaf_of.len() != objects.len() {
+            self.clear();
+            for obj_idx in 0..objects.len() {
+                let leaf = self.allocate(self.bounds[obj_idx].inflate(self.margin), obj_idx);
+                self.leaf_of.push(leaf);
+                self.insert_leaf(leaf);
+            }
+            return;
+        }
+
+        for obj_idx in 0..objects.len() {
+            let leaf = self.leaf_of[obj_idx];
+            let bounds = self.bounds[obj_idx];
+            if !self.nodes[leaf].aabb.contains(&bounds) {
+                self.remove_leaf(leaf);
+                self.nodes[leaf].aabb = bounds.inflate(self.margin);
+                self.nodes[leaf].parent = NULL;
+                self.insert_leaf(leaf);
+            }
+        }
+    }
+}
+impl Default for DynamicAabbTree {
+    fn default() -> Self {
+        Self::new(DEFAULT_MARGIN)
+    }
+}
+impl BroadPhase for DynamicAabbTree {
+    fn find_pairs(&mut self, objects: &[VerletObject], pairs: &mut Vec<(usize, usize)>) {
+        self.bounds.clear();
+        self.bounds.extend(objects.iter().map(Aabb::of));
+        self.update(objects);
+
+        if self.root == NULL {
+            return;
+        }
+        for (i, bounds) in self.bounds.iter().enumerate() {
+            self.stack.clear();
+            self.stack.push(self.root);
+            while let Some(idx) = self.stack.pop() {
+                let node = &self.nodes[idx];
+                if !node.aabb.overlaps(bounds) {
+                    continue;
+                }
+                if node.is_leaf() {
+                    let j = node.object;
+                    if j > i && bounds.overlaps(&self.bounds[j]) {
+                        pairs.push((i, j));
+                    }
+                } else {
+                    self.stack.push(node.left);
+                    self.stack.push(node.right);
+                }
+            }
+        }
+    }
+}
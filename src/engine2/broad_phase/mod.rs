@@ -0,0 +1,143 @@
+pub mod bvh;
+pub mod sweep_and_prune;
+pub mod uniform_grid;
+
+use itertools::Itertools;
+
+use crate::engine2::{aabb::Aabb, verlet_object::VerletObject};
+
+/// Finds pairs of objects that might collide.
+///
+/// Implementations append every pair ``(i, j)``, ``i < j``, of indices into ``objects``
+/// whose bounding boxes overlap, exactly once and in no particular order. Narrow phase
+/// (the actual circle test) is left to the caller.
+pub trait BroadPhase {
+    fn find_pairs(&mut self, objects: &[VerletObject], pairs: &mut Vec<(usize, usize)>);
+}
+
+/// Tests every pair, O(n²). Reference implementation for the other ones.
+#[derive(Default)]
+pub struct BruteForce;
+impl BroadPhase for BruteForce {
+    fn find_pairs(&mut self, objects: &[VerletObject], pairs: &mut Vec<(usize, usize)>) {
+        let bounds: Vec<Aabb> = objects.iter().map(Aabb::of).collect();
+        (0..objects.len())
+            .tuple_combinations()
+            .filter(|&(i, j)| bounds[i].overlaps(&bounds[j]))
+            .for_each(|pair| pairs.push(pair));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec2;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::{
+        bvh::DynamicAabbTree, sweep_and_prune::SweepAndPrune, uniform_grid::UniformGrid,
+        BroadPhase, BruteForce,
+    };
+    use crate::engine2::verlet_object::VerletObject;
+
+    fn random_objects(
+        seed: u64,
+        count: usize,
+        spread: f32,
+        radii: (f32, f32),
+    ) -> Vec<VerletObject> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|_| {
+                let center = vec2(
+                    rng.gen_range(-spread..spread),
+                    rng.gen_range(-spread..spread),
+                );
+                VerletObject::new(center, rng.gen_range(radii.0..radii.1), [1.0; 4])
+            })
+            .collect()
+    }
+
+    fn sorted_pairs(
+        broad_phase: &mut dyn BroadPhase,
+        objects: &[VerletObject],
+    ) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        broad_phase.find_pairs(objects, &mut pairs);
+        assert!(pairs.iter().all(|(i, j)| i < j));
+        pairs.sort_unstable();
+        pairs
+    }
+
+    fn implementations() -> Vec<Box<dyn BroadPhase>> {
+        vec![
+            Box::new(UniformGrid::default()),
+            Box::new(SweepAndPrune::default()),
+            Box::new(DynamicAabbTree::default()),
+        ]
+    }
+
+    fn assert_matches_brute_force(objects: &[VerletObject]) {
+        let expected = sorted_pairs(&mut BruteForce, objects);
+        for mut broad_phase in implementations() {
+            assert_eq!(sorted_pairs(broad_phase.as_mut(), objects), expected);
+        }
+    }
+
+    #[test]
+    fn empty_and_single_object() {
+        assert_matches_brute_force(&[]);
+        assert_matches_brute_force(&random_objects(0, 1, 1.0, (0.01, 0.02)));
+    }
+
+    #[test]
+    fn uniform_radii() {
+        assert_matches_brute_force(&random_objects(1, 1500, 0.5, (0.01, 0.010_000_1)));
+    }
+
+    #[test]
+    fn mixed_radii() {
+        assert_matches_brute_force(&random_objects(2, 1500, 0.9, (0.001, 0.05)));
+    }
+
+    #[test]
+    fn dense_cluster() {
+        assert_matches_brute_force(&random_objects(3, 800, 0.05, (0.002, 0.01)));
+    }
+
+    #[test]
+    fn coincident_centers() {
+        let objects: Vec<VerletObject> = (0..20u8)
+            .map(|i| {
+                VerletObject::new(
+                    vec2(0.25, -0.5),
+                    0.001f32.mul_add(f32::from(i), 0.01),
+                    [1.0; 4],
+                )
+            })
+            .collect();
+        assert_matches_brute_force(&objects);
+    }
+
+    #[test]
+    fn stays_correct_while_objects_move() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut objects = random_objects(5, 600, 0.8, (0.005, 0.02));
+        let mut stateful = implementations();
+
+        for _ in 0..10 {
+            for obj in &mut objects {
+                obj.shift(vec2(rng.gen_range(-0.03..0.03), rng.gen_range(-0.03..0.03)));
+            }
+            let expected = sorted_pairs(&mut BruteForce, &objects);
+            for broad_phase in &mut stateful {
+                assert_eq!(sorted_pairs(broad_phase.as_mut(), &objects), expected);
+            }
+        }
+
+        objects.truncate(350);
+        let expected = sorted_pairs(&mut BruteForce, &objects);
+        for broad_phase in &mut stateful {
+            assert_eq!(sorted_pairs(broad_phase.as_mut(), &objects), expected);
+        }
+    }
+}
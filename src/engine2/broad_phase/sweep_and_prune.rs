@@ -0,0 +1,35 @@
+use crate::engine2::{aabb::Aabb, broad_phase::BroadPhase, verlet_object::VerletObject};
+
+/// Sorts objects along the x axis and only tests those whose x intervals overlap.
+///
+/// The order is kept between calls, objects move little per step so sorting the
+/// previous order is close to linear.
+#[derive(Default)]
+pub struct SweepAndPrune {
+    order: Vec<usize>,
+    bounds: Vec<Aabb>,
+}
+impl BroadPhase for SweepAndPrune {
+    fn find_pairs(&mut self, objects: &[VerletObject], pairs: &mut Vec<(usize, usize)>) {
+        self.bounds.clear();
+        self.bounds.extend(objects.iter().map(Aabb::of));
+
+        if self.order.len() != objects.len() {
+            self.order = (0..objects.len()).collect();
+        }
+        let bounds = &self.bounds;
+        self.order
+            .sort_by(|&i, &j| bounds[i].min.x.total_cmp(&bounds[j].min.x));
+
+        for (n, &i) in self.order.iter().enumerate() {
+            for &j in &self.order[n + 1..] {
+                if bounds[j].min.x >= bounds[i].max.x {
+                    break;
+                }
+                if bounds[i].overlaps(&bounds[j]) {
+                    pairs.push((i.min(j), i.max(j)));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use nalgebra_glm::{vec2, Vec2};
+
+use crate::engine2::{aabb::Aabb, broad_phase::BroadPhase, verlet_object::VerletObject};
+
+// a little slack so float rounding never puts overlapping objects two cells apart
+const CELL_SIZE_SLACK: f32 = 1.001;
+// keeps sparse scenes from allocating huge grids
+const MAX_CELLS_PER_OBJECT: usize = 4;
+
+// own cell is handled separately, these cover every neighbour exactly once
+const FORWARD_NEIGHBOURS: [(isize, isize); 4] = [(1, 0), (-1, 1), (0, 1), (1, 1)];
+
+/// Uniform grid sized after the largest object.
+///
+/// Cells are at least as wide as the largest diameter, so every object is stored in a
+/// single cell (the one holding its center) and can only overlap objects from the same or
+/// from one of the eight neighbouring cells.
+#[derive(Default)]
+pub struct UniformGrid {
+    origin: Vec2,
+    cell_size: f32,
+    max_radius: f32,
+    cols: usize,
+    rows: usize,
+    cell_of: Vec<usize>,
+    cell_start: Vec<usize>,
+    sorted: Vec<usize>,
+    bounds: Vec<Aabb>,
+}
+impl UniformGrid {
+    #[must_use]
+    pub const fn cols(&self) -> usize {
+        self.cols
+    }
+
+    #[must_use]
+    pub const fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[must_use]
+    pub const fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Largest radius among the objects of the last ``rebuild``.
+    #[must_use]
+    pub const fn max_radius(&self) -> f32 {
+        self.max_radius
+    }
+
+    /// Area covered by the grid cells.
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn bounds(&self) -> Aabb {
+        let size = vec2(self.cols as f32, self.rows as f32) * self.cell_size;
+        Aabb::new(self.origin, self.origin + size)
+    }
+
+    /// Inclusive column and row ranges of the cells whose objects can overlap ``area``.
+    #[must_use]
+    pub fn cells_overlapping(&self, area: &Aabb) -> ((usize, usize), (usize, usize)) {
+        let area = area.inflate(self.max_radius);
+        let (min_col, min_row) = self.cell_coords(area.min);
+        let (max_col, max_row) = self.cell_coords(area.max);
+        ((min_col, max_col), (min_row, max_row))
+    }
+
+    /// Object indices stored in the cell, valid after the last ``rebuild``.
+    #[must_use]
+    pub fn cell(&self, col: usize, row: usize) -> &[usize] {
+        let cell = row * self.cols + col;
+        &self.sorted[self.cell_start[cell]..self.cell_start[cell + 1]]
+    }
+
+    /// Sizes the grid to the current objects and sorts them into cells.
+    pub fn rebuild(&mut self, objects: &[VerletObject]) {
+        self.bounds.clear();
+        self.bounds.extend(objects.iter().map(Aabb::of));
+
+        let Some(first) = self.bounds.first() else {
+            self.cols = 0;
+            self.rows = 0;
+            self.cell_start.clear();
+            self.cell_start.push(0);
+            self.sorted.clear();
+            return;
+        };
+        let extent = self.bounds.iter().fold(*first, |acc, b| acc.merge(b));
+        self.max_radius = objects
+            .iter()
+            .map(VerletObject::get_radius)
+            .fold(0.0, f32::max);
+
+        self.origin = extent.min;
+        self.cell_size = (2.0 * self.max_radius * CELL_SIZE_SLACK).max(f32::EPSILON);
+        let max_cells = objects.len() * MAX_CELLS_PER_OBJECT + 16;
+        loop {
+            self.cols = self.cells_along(extent.max.x - extent.min.x);
+            self.rows = self.cells_along(extent.max.y - extent.min.y);
+            if self.cols.saturating_mul(self.rows) <= max_cells {
+                break;
+            }
+            self.cell_size *= 2.0;
+        }
+
+        // counting sort of objects by cell
+        let cells = self.cols * self.rows;
+        self.cell_start.clear();
+        self.cell_start.resize(cells + 1, 0);
+        self.cell_of.clear();
+        for obj in objects {
+            let (col, row) = self.cell_coords(obj.get_center());
+            let cell = row * self.cols + col;
+            self.cell_of.push(cell);
+            self.cell_start[cell + 1] += 1;
+        }
+        for cell in 0..cells {
+            self.cell_start[cell + 1] += self.cell_start[cell];
+        }
+        self.sorted.clear();
+        self.sorted.resize(objects.len(), 0);
+        let mut next = self.cell_start.clone();
+        for (idx, &cell) in self.cell_of.iter().enumerate() {
+            self.sorted[next[cell]] = idx;
+            next[cell] += 1;
+        }
+    }
+
+    /// Column and row of the cell containing ``point``, clamped to the grid.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    #[must_use]
+    pub fn cell_coords(&self, point: Vec2) -> (usize, usize) {
+        let local = (point - self.origin) / self.cell_size;
+        let local = vec2(local.x.max(0.0), local.y.max(0.0));
+        (
+            (local.x as usize).min(self.cols.saturating_sub(1)),
+            (local.y as usize).min(self.rows.saturating_sub(1)),
+        )
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn cells_along(&self, length: f32) -> usize {
+        (length / self.cell_size).floor() as usize + 1
+    }
+
+    fn push_pair(&self, i: usize, j: usize, pairs: &mut Vec<(usize, usize)>) {
+        if self.bounds[i].overlaps(&self.bounds[j]) {
+            pairs.push((i.min(j), i.max(j)));
+        }
+    }
+
+    /// Pairs of overlapping objects whose cells are ``(col, row)`` and the forward
+    /// neighbours of it.
+    pub fn cell_pairs(&self, col: usize, row: usize, pairs: &mut Vec<(usize, usize)>) {
+        let own = self.cell(col, row);
+        for (n, &i) in own.iter().enumerate() {
+            for &j in &own[n + 1..] {
+                self.push_pair(i, j, pairs);
+            }
+        }
+
+        for (d_col, d_row) in FORWARD_NEIGHBOURS {
+            let (Some(other_col), Some(other_row)) =
+                (col.checked_add_signed(d_col), row.checked_add_signed(d_row))
+            else {
+                continue;
+            };
+            if other_col >= self.cols || other_row >= self.rows {
+                continue;
+            }
+            let other = self.cell(other_col, other_row);
+            for &i in own {
+                for &j in other {
+                    self.push_pair(i, j, pairs);
+                }
+            }
+        }
+    }
+}
+impl BroadPhase for UniformGrid {
+    fn find_pairs(&mut self, objects: &[VerletObject], pairs: &mut Vec<(usize, usize)>) {
+        self.rebuild(objects);
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                self.cell_pairs(col, row, pairs);
+            }
+        }
+    }
+}
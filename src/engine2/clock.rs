@@ -0,0 +1,105 @@
+use crate::engine2::world::World;
+
+const DEFAULT_TICK: f32 = 1.0 / 60.0;
+const DEFAULT_SUBSTEPS: usize = 4;
+const DEFAULT_MAX_TICKS_PER_FRAME: usize = 5;
+
+/// Fixed timestep driver for a ``World``.
+///
+/// Wall-clock frame time is collected in an accumulator and the solver is advanced in
+/// ticks of constant length, so the simulation behaves the same regardless of frame rate.
+/// When a frame takes too long only ``max_ticks_per_frame`` ticks are simulated and the
+/// remaining backlog is dropped instead of spiralling further behind.
+pub struct SimulationClock {
+    tick: f32,
+    substeps: usize,
+    max_ticks_per_frame: usize,
+    accumulator: f32,
+    ticks: u64,
+}
+impl SimulationClock {
+    /// # Panics
+    /// If ``tick`` isn't positive or ``substeps`` is zero.
+    #[must_use]
+    pub fn new(tick: f32, substeps: usize) -> Self {
+        assert!(tick > 0.0, "Tick has to be longer than zero.");
+        assert!(substeps > 0, "At least one substep is needed.");
+        Self {
+            tick,
+            substeps,
+            max_ticks_per_frame: DEFAULT_MAX_TICKS_PER_FRAME,
+            accumulator: 0.0,
+            ticks: 0,
+        }
+    }
+
+    /// # Panics
+    /// If ``max_ticks_per_frame`` is zero, the clock would never simulate.
+    #[must_use]
+    pub const fn with_max_ticks_per_frame(mut self, max_ticks_per_frame: usize) -> Self {
+        assert!(
+            max_ticks_per_frame > 0,
+            "At least one tick per frame is needed."
+        );
+        self.max_ticks_per_frame = max_ticks_per_frame;
+        self
+    }
+
+    /// Adds ``frame_dt`` seconds to the accumulator and runs as many fixed ticks as fit.
+    /// Returns the number of ticks simulated.
+    pub fn advance(&mut self, world: &mut World, frame_dt: f32) -> usize {
+        self.accumulator += frame_dt;
+
+        let mut simulated = 0;
+        for _ in 0..self.max_ticks_per_frame {
+            if self.accumulator < self.tick {
+                break;
+            }
+            world.update(self.tick, self.substeps);
+            self.accumulator -= self.tick;
+            self.ticks += 1;
+            simulated += 1;
+        }
+
+        // catch-up cap reached, forget about the time we couldn't simulate
+        if simulated == self.max_ticks_per_frame {
+            self.accumulator = self.accumulator.min(self.tick);
+        }
+
+        simulated
+    }
+
+    /// How far, in the ``[0, 1]`` range, the real time is between the last simulated tick
+    /// and the next one. Meant to be used for render interpolation.
+    #[must_use]
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.tick).clamp(0.0, 1.0)
+    }
+
+    #[must_use]
+    pub const fn tick(&self) -> f32 {
+        self.tick
+    }
+
+    #[must_use]
+    pub const fn substeps(&self) -> usize {
+        self.substeps
+    }
+
+    #[must_use]
+    pub const fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Simulated time in seconds.
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn elapsed(&self) -> f64 {
+        self.ticks as f64 * f64::from(self.tick)
+    }
+}
+impl Default for SimulationClock {
+    fn default() -> Self {
+        Self::new(DEFAULT_TICK, DEFAULT_SUBSTEPS)
+    }
+}
@@ -0,0 +1,62 @@
+use nalgebra_glm::Vec2;
+
+use crate::engine2::verlet_object::VerletObject;
+
+/// Overlap of two objects found, and resolved, by ``solve_collision``.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Penetration {
+    pub depth: f32,
+    /// Unit vector pointing from the first object to the second one.
+    pub normal: Vec2,
+    pub inverse_mass_sum: f32,
+}
+
+/// Pushes two overlapping objects apart, heavier one moving less. Returns ``None`` if the
+/// objects don't overlap or both are static.
+pub fn solve_collision(obj1: &mut VerletObject, obj2: &mut VerletObject) -> Option<Penetration> {
+    let centers_distance = obj2.get_center().metric_distance(&obj1.get_center());
+    let radius_sum = obj2.get_radius() + obj1.get_radius();
+
+    if centers_distance < radius_sum {
+        let (w1, w2) = correction_weights(obj1, obj2)?;
+        let delta_versor = (obj2.get_center() - obj1.get_center()).normalize();
+
+        let depth = radius_sum - centers_distance;
+        let adjustment_vector = delta_versor * depth;
+
+        obj1.shift(-w1 * adjustment_vector);
+        obj2.shift(w2 * adjustment_vector);
+
+        return Some(Penetration {
+            depth,
+            normal: delta_versor,
+            inverse_mass_sum: obj1.get_inverse_mass() + obj2.get_inverse_mass(),
+        });
+    }
+    None
+}
+
+/// Share of a positional correction each object takes, proportional to its inverse mass.
+/// ``None`` if both objects are static.
+pub fn correction_weights(obj1: &VerletObject, obj2: &VerletObject) -> Option<(f32, f32)> {
+    let inverse_mass_sum = obj1.get_inverse_mass() + obj2.get_inverse_mass();
+    if inverse_mass_sum <= 0.0 {
+        return None;
+    }
+    Some((
+        obj1.get_inverse_mass() / inverse_mass_sum,
+        obj2.get_inverse_mass() / inverse_mass_sum,
+    ))
+}
+
+/// Two distinct mutable elements of a slice.
+pub fn pair_mut<T>(items: &mut [T], i: usize, j: usize) -> (&mut T, &mut T) {
+    assert_ne!(i, j, "pair_mut() requires two different indices");
+    if i < j {
+        let (head, tail) = items.split_at_mut(j);
+        (&mut head[i], &mut tail[0])
+    } else {
+        let (head, tail) = items.split_at_mut(i);
+        (&mut tail[0], &mut head[j])
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+
+use nalgebra_glm::Vec2;
+
+use crate::engine2::{collision::Penetration, handle::ObjectHandle};
+
+/// Two objects touching during a step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    pub a: ObjectHandle,
+    pub b: ObjectHandle,
+    /// Deepest penetration seen during the step.
+    pub depth: f32,
+    /// Unit vector pointing from ``a`` to ``b``.
+    pub normal: Vec2,
+    /// Magnitude of the impulse the solver applied along the normal, summed over substeps.
+    pub impulse: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContactEvent {
+    /// The objects touch and didn't during the previous step.
+    Begin(Contact),
+    /// The objects touched during the previous step too.
+    Persist(Contact),
+    /// The objects touched during the previous step but don't anymore. Handles may be stale
+    /// if the objects got removed.
+    End { a: ObjectHandle, b: ObjectHandle },
+}
+
+pub type ContactListener = Box<dyn FnMut(&ContactEvent)>;
+
+/// Collects contacts of a step and turns them into events by comparing to the step before.
+///
+/// Contacts and events come ordered by the handles of the objects, so they are the same
+/// every time a step is simulated.
+#[derive(Default)]
+pub struct ContactTracker {
+    current: BTreeMap<(ObjectHandle, ObjectHandle), Contact>,
+    previous: BTreeMap<(ObjectHandle, ObjectHandle), Contact>,
+    events: Vec<ContactEvent>,
+    listener: Option<ContactListener>,
+}
+impl ContactTracker {
+    pub(crate) fn begin_step(&mut self) {
+        std::mem::swap(&mut self.current, &mut self.previous);
+        self.current.clear();
+        self.events.clear();
+    }
+
+    /// Forgets all contacts, e.g. after the objects got replaced.
+    pub(crate) fn reset(&mut self) {
+        self.current.clear();
+        self.previous.clear();
+        self.events.clear();
+    }
+
+    /// Records an overlap resolved during a substep of length ``dt``.
+    pub(crate) fn record(
+        &mut self,
+        a: ObjectHandle,
+        b: ObjectHandle,
+        penetration: &Penetration,
+        dt: f32,
+    ) {
+        // positional correction of `depth` in one substep equals this velocity impulse
+        let impulse = penetration.depth / (dt * penetration.inverse_mass_sum);
+        let (key, normal) = if a <= b {
+            ((a, b), penetration.normal)
+        } else {
+            ((b, a), -penetration.normal)
+        };
+
+        self.current
+            .entry(key)
+            .and_modify(|contact| {
+                contact.depth = contact.depth.max(penetration.depth);
+                contact.normal = normal;
+                contact.impulse += impulse;
+            })
+            .or_insert(Contact {
+                a: key.0,
+                b: key.1,
+                depth: penetration.depth,
+                normal,
+                impulse,
+            });
+    }
+
+    pub(crate) fn finish_step(&mut self) {
+        for (key, contact) in &self.current {
+            self.events.push(if self.previous.contains_key(key) {
+                ContactEvent::Persist(*contact)
+            } else {
+                ContactEvent::Begin(*contact)
+            });
+        }
+        for &(a, b) in self.previous.keys() {
+            if !self.current.contains_key(&(a, b)) {
+                self.events.push(ContactEvent::End { a, b });
+            }
+        }
+
+        if let Some(listener) = &mut self.listener {
+            self.events.iter().for_each(listener);
+        }
+    }
+
+    /// Contacts of the last step.
+    pub fn contacts(&self) -> impl Iterator<Item = &Contact> {
+        self.current.values()
+    }
+
+    /// Events of the last step.
+    #[must_use]
+    pub fn events(&self) -> &[ContactEvent] {
+        &self.events
+    }
+
+    /// Called for every event at the end of each step.
+    pub fn set_listener(&mut self, listener: Option<ContactListener>) {
+        self.listener = listener;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec2;
+
+    use super::{ContactEvent, ContactTracker};
+    use crate::engine2::{collision::Penetration, handle::HandleMap};
+
+    const DT: f32 = 0.01;
+
+    fn penetration(depth: f32) -> Penetration {
+        Penetration {
+            depth,
+            normal: vec2(1.0, 0.0),
+            inverse_mass_sum: 2.0,
+        }
+    }
+
+    #[test]
+    fn begin_persist_end() {
+        let mut handles = HandleMap::default();
+        let (a, b) = (handles.push(), handles.push());
+        let mut tracker = ContactTracker::default();
+
+        tracker.begin_step();
+        tracker.record(a, b, &penetration(0.1), DT);
+        tracker.finish_step();
+        assert!(matches!(tracker.events(), [ContactEvent::Begin(c)] if (c.a, c.b) == (a, b)));
+
+        // reversed order is the same pair, the normal flips to keep pointing from a to b
+        tracker.begin_step();
+        tracker.record(b, a, &penetration(0.2), DT);
+        tracker.record(b, a, &penetration(0.05), DT);
+        tracker.finish_step();
+        let [ContactEvent::Persist(contact)] = tracker.events() else {
+            panic!(
+                "expected a single persist event, got {:?}",
+                tracker.events()
+            );
+        };
+        assert!((contact.depth - 0.2).abs() < 1e-6);
+        assert_eq!(contact.normal, vec2(-1.0, 0.0));
+        assert!((contact.impulse - 0.25 / (DT * 2.0)).abs() < 1e-3);
+
+        tracker.begin_step();
+        tracker.finish_step();
+        assert_eq!(tracker.events(), [ContactEvent::End { a, b }]);
+
+        tracker.begin_step();
+        tracker.finish_step();
+        assert!(tracker.events().is_empty());
+    }
+
+    #[test]
+    fn events_are_ordered_by_handles() {
+        let mut handles = HandleMap::default();
+        let objects: Vec<_> = (0..6).map(|_| handles.push()).collect();
+        let mut tracker = ContactTracker::default();
+
+        tracker.begin_step();
+        for &(i, j) in &[(4, 5), (0, 3), (2, 1), (0, 1)] {
+            tracker.record(objects[i], objects[j], &penetration(0.1), DT);
+        }
+        tracker.finish_step();
+
+        let pairs: Vec<_> = tracker.contacts().map(|c| (c.a, c.b)).collect();
+        let expected = [(0, 1), (0, 3), (1, 2), (4, 5)].map(|(i, j)| (objects[i], objects[j]));
+        assert_eq!(pairs, expected);
+        let event_pairs: Vec<_> = tracker
+            .events()
+            .iter()
+            .map(|event| match event {
+                ContactEvent::Begin(c) | ContactEvent::Persist(c) => (c.a, c.b),
+                ContactEvent::End { a, b } => (*a, *b),
+            })
+            .collect();
+        assert_eq!(event_pairs, expected);
+    }
+}
@@ -0,0 +1,369 @@
+use nalgebra_glm::{vec2, Vec2};
+
+const UP: Vec2 = Vec2::new(0.0, 1.0);
+
+/// Region the objects of a ``World`` are kept inside of.
+///
+/// Every shape is described by a signed distance (negative inside) so containers can be
+/// freely combined: a ``Union`` keeps objects inside any of its members and a ``Carved``
+/// container keeps them inside ``outer`` while pushing them out of ``obstacle``.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Container {
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+    /// Axis-aligned box.
+    Rectangle {
+        center: Vec2,
+        width: f32,
+        height: f32,
+    },
+    /// Convex polygon, vertices in counter-clockwise order.
+    Polygon {
+        vertices: Vec<Vec2>,
+    },
+    /// Segment from ``a`` to ``b`` inflated by ``radius``.
+    Capsule {
+        a: Vec2,
+        b: Vec2,
+        radius: f32,
+    },
+    Union(Vec<Self>),
+    Carved {
+        outer: Box<Self>,
+        obstacle: Box<Self>,
+    },
+}
+
+/// Where and how deep an object sticks out of a ``Container``.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContainerContact {
+    /// Unit vector pointing out of the allowed region.
+    pub normal: Vec2,
+    pub depth: f32,
+}
+impl ContainerContact {
+    /// Offset that moves the object back to the boundary.
+    #[must_use]
+    pub fn correction(&self) -> Vec2 {
+        -self.normal * self.depth
+    }
+}
+
+/// How objects bounce off and slide along a ``Container``.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContactMaterial {
+    /// Fraction of the normal velocity kept after a bounce, ``0.0`` is perfectly inelastic.
+    pub restitution: f32,
+    /// Coulomb friction coefficient, limits the tangential velocity change to
+    /// ``friction`` times the normal one.
+    pub friction: f32,
+}
+impl ContactMaterial {
+    #[must_use]
+    pub const fn new(restitution: f32, friction: f32) -> Self {
+        Self {
+            restitution,
+            friction,
+        }
+    }
+
+    /// Velocity after hitting a wall with outward ``normal``. Works on any velocity
+    /// representation, including the per step displacement of a Verlet object.
+    #[must_use]
+    pub fn respond(&self, velocity: Vec2, normal: Vec2) -> Vec2 {
+        let normal_speed = velocity.dot(&normal);
+        if normal_speed <= 0.0 {
+            // already separating
+            return velocity;
+        }
+        let tangential = velocity - normal * normal_speed;
+        let tangential_speed = tangential.magnitude();
+
+        let normal_change = (1.0 + self.restitution) * normal_speed;
+        let tangential_change = (self.friction * normal_change).min(tangential_speed);
+        let tangential = if tangential_speed > f32::EPSILON {
+            tangential * (1.0 - tangential_change / tangential_speed)
+        } else {
+            tangential
+        };
+
+        tangential - normal * (self.restitution * normal_speed)
+    }
+}
+impl Default for ContactMaterial {
+    fn default() -> Self {
+        Self::new(0.0, 0.0)
+    }
+}
+
+impl Container {
+    #[must_use]
+    pub const fn circle(center: Vec2, radius: f32) -> Self {
+        Self::Circle { center, radius }
+    }
+
+    #[must_use]
+    pub const fn rectangle(center: Vec2, width: f32, height: f32) -> Self {
+        Self::Rectangle {
+            center,
+            width,
+            height,
+        }
+    }
+
+    /// Vertices may be given in any winding, they are stored counter-clockwise.
+    ///
+    /// # Panics
+    /// If the polygon isn't convex or has fewer than three vertices, repeated vertices or no
+    /// area.
+    #[must_use]
+    pub fn polygon(mut vertices: Vec<Vec2>) -> Self {
+        if signed_area(&vertices) < 0.0 {
+            vertices.reverse();
+        }
+        if let Err(reason) = check_polygon(&vertices) {
+            panic!("Invalid container polygon: {reason}.");
+        }
+        Self::Polygon { vertices }
+    }
+
+    #[must_use]
+    pub const fn capsule(a: Vec2, b: Vec2, radius: f32) -> Self {
+        Self::Capsule { a, b, radius }
+    }
+
+    /// Lets objects live in ``self`` or in ``other``.
+    #[must_use]
+    pub fn union(self, other: Self) -> Self {
+        match self {
+            Self::Union(mut members) => {
+                members.push(other);
+                Self::Union(members)
+            }
+            container => Self::Union(vec![container, other]),
+        }
+    }
+
+    /// Keeps objects out of ``obstacle`` while still inside ``self``.
+    #[must_use]
+    pub fn carve(self, obstacle: Self) -> Self {
+        Self::Carved {
+            outer: Box::new(self),
+            obstacle: Box::new(obstacle),
+        }
+    }
+
+    /// Moves the whole container by ``offset``.
+    pub fn translate(&mut self, offset: Vec2) {
+        match self {
+            Self::Circle { center, .. } | Self::Rectangle { center, .. } => *center += offset,
+            Self::Polygon { vertices } => vertices.iter_mut().for_each(|v| *v += offset),
+            Self::Capsule { a, b, .. } => {
+                *a += offset;
+                *b += offset;
+            }
+            Self::Union(members) => members.iter_mut().for_each(|c| c.translate(offset)),
+            Self::Carved { outer, obstacle } => {
+                outer.translate(offset);
+                obstacle.translate(offset);
+            }
+        }
+    }
+
+    /// Scales the whole container by ``factor`` relative to ``pivot``.
+    pub fn scale(&mut self, pivot: Vec2, factor: f32) {
+        let scaled = |p: Vec2| pivot + (p - pivot) * factor;
+        match self {
+            Self::Circle { center, radius } => {
+                *center = scaled(*center);
+                *radius *= factor;
+            }
+            Self::Rectangle {
+                center,
+                width,
+                height,
+            } => {
+                *center = scaled(*center);
+                *width *= factor;
+                *height *= factor;
+            }
+            Self::Polygon { vertices } => vertices.iter_mut().for_each(|v| *v = scaled(*v)),
+            Self::Capsule { a, b, radius } => {
+                *a = scaled(*a);
+                *b = scaled(*b);
+                *radius *= factor;
+            }
+            Self::Union(members) => members.iter_mut().for_each(|c| c.scale(pivot, factor)),
+            Self::Carved { outer, obstacle } => {
+                outer.scale(pivot, factor);
+                obstacle.scale(pivot, factor);
+            }
+        }
+    }
+
+    /// Signed distance from ``point`` to the boundary (negative inside) together with the
+    /// outward unit normal of the closest boundary feature.
+    #[must_use]
+    pub fn signed_distance(&self, point: Vec2) -> (f32, Vec2) {
+        match self {
+            Self::Circle { center, radius } => {
+                let delta = point - center;
+                let distance = delta.magnitude();
+                (distance - radius, direction_or_up(delta, distance))
+            }
+            Self::Rectangle {
+                center,
+                width,
+                height,
+            } => rectangle_distance(point - center, vec2(width / 2.0, height / 2.0)),
+            Self::Polygon { vertices } => polygon_distance(point, vertices),
+            Self::Capsule { a, b, radius } => {
+                let delta = point - closest_on_segment(point, *a, *b);
+                let distance = delta.magnitude();
+                (distance - radius, direction_or_up(delta, distance))
+            }
+            Self::Union(members) => members
+                .iter()
+                .map(|c| c.signed_distance(point))
+                .min_by(|(d1, _), (d2, _)| d1.total_cmp(d2))
+                .unwrap_or((f32::INFINITY, UP)),
+            Self::Carved { outer, obstacle } => {
+                let (outer_distance, outer_normal) = outer.signed_distance(point);
+                let (obstacle_distance, obstacle_normal) = obstacle.signed_distance(point);
+                if outer_distance > -obstacle_distance {
+                    (outer_distance, outer_normal)
+                } else {
+                    (-obstacle_distance, -obstacle_normal)
+                }
+            }
+        }
+    }
+
+    /// ``None`` if a disc of ``radius`` at ``center`` fits entirely inside the container.
+    #[must_use]
+    pub fn contact(&self, center: Vec2, radius: f32) -> Option<ContainerContact> {
+        let (distance, normal) = self.signed_distance(center);
+        let depth = distance + radius;
+        if depth > 0.0 {
+            Some(ContainerContact { normal, depth })
+        } else {
+            None
+        }
+    }
+}
+/// Checks that counter-clockwise ``vertices`` make a convex polygon ``Container::Polygon``
+/// can handle.
+pub(crate) fn check_polygon(vertices: &[Vec2]) -> Result<(), &'static str> {
+    if vertices.len() < 3 {
+        return Err("fewer than three vertices");
+    }
+    if signed_area(vertices) <= f32::EPSILON {
+        return Err("no area or clockwise winding");
+    }
+    let edges: Vec<Vec2> = vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| b - a)
+        .collect();
+    if edges.iter().any(|edge| edge.magnitude() <= f32::EPSILON) {
+        return Err("repeated vertices");
+    }
+    let turns_right = edges
+        .iter()
+        .zip(edges.iter().cycle().skip(1))
+        .any(|(e1, e2)| e1.x.mul_add(e2.y, -(e1.y * e2.x)) < -f32::EPSILON);
+    if turns_right {
+        return Err("not convex");
+    }
+    Ok(())
+}
+
+impl Default for Container {
+    fn default() -> Self {
+        Self::rectangle(vec2(0.0, 0.0), 1.8, 1.8)
+    }
+}
+
+fn direction_or_up(delta: Vec2, length: f32) -> Vec2 {
+    if length > f32::EPSILON {
+        delta / length
+    } else {
+        UP
+    }
+}
+
+fn closest_on_segment(point: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let ab = b - a;
+    let length_squared = ab.magnitude_squared();
+    if length_squared <= f32::EPSILON {
+        return a;
+    }
+    let t = ((point - a).dot(&ab) / length_squared).clamp(0.0, 1.0);
+    a + ab * t
+}
+
+fn rectangle_distance(local: Vec2, half_extents: Vec2) -> (f32, Vec2) {
+    let q = local.abs() - half_extents;
+    let sign = vec2(local.x.signum(), local.y.signum());
+    if q.x > 0.0 || q.y > 0.0 {
+        let outside = vec2(q.x.max(0.0), q.y.max(0.0));
+        let distance = outside.magnitude();
+        (
+            distance,
+            direction_or_up(outside.component_mul(&sign), distance),
+        )
+    } else if q.x > q.y {
+        (q.x, vec2(sign.x, 0.0))
+    } else {
+        (q.y, vec2(0.0, sign.y))
+    }
+}
+
+fn polygon_distance(point: Vec2, vertices: &[Vec2]) -> (f32, Vec2) {
+    let edges = || {
+        vertices
+            .iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .map(|(a, b)| (*a, *b))
+    };
+
+    let mut inside = true;
+    let mut max_plane_distance = f32::NEG_INFINITY;
+    let mut max_plane_normal = UP;
+    for (a, b) in edges() {
+        let edge = b - a;
+        let normal = vec2(edge.y, -edge.x).normalize();
+        let plane_distance = (point - a).dot(&normal);
+        if plane_distance > 0.0 {
+            inside = false;
+        }
+        if plane_distance > max_plane_distance {
+            max_plane_distance = plane_distance;
+            max_plane_normal = normal;
+        }
+    }
+
+    if inside {
+        return (max_plane_distance, max_plane_normal);
+    }
+
+    edges()
+        .map(|(a, b)| {
+            let delta = point - closest_on_segment(point, a, b);
+            let distance = delta.magnitude();
+            (distance, direction_or_up(delta, distance))
+        })
+        .min_by(|(d1, _), (d2, _)| d1.total_cmp(d2))
+        .unwrap_or((f32::INFINITY, UP))
+}
+
+fn signed_area(vertices: &[Vec2]) -> f32 {
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| a.x.mul_add(b.y, -(b.x * a.y)))
+        .sum::<f32>()
+        / 2.0
+}
@@ -0,0 +1,188 @@
+use nalgebra_glm::Vec2;
+
+use crate::engine2::{container::Container, verlet_object::VerletObject};
+
+/// Something that pushes objects around, evaluated by the ``World`` every substep before
+/// positions are integrated. Implementations add to the objects' acceleration.
+pub trait ForceGenerator {
+    fn apply(&self, objects: &mut [VerletObject], dt: f32);
+
+    /// Copy of the generator that can be written to a snapshot, ``None`` if it can't be
+    /// saved (e.g. it wraps a closure).
+    fn saved(&self) -> Option<SavedForce> {
+        None
+    }
+}
+
+/// Built-in force generators that can be saved and restored.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SavedForce {
+    UniformGravity(UniformGravity),
+    PointAttractor(PointAttractor),
+    LinearDrag(LinearDrag),
+    GravityZone(GravityZone),
+}
+impl SavedForce {
+    #[must_use]
+    pub fn into_generator(self) -> Box<dyn ForceGenerator> {
+        match self {
+            Self::UniformGravity(force) => Box::new(force),
+            Self::PointAttractor(force) => Box::new(force),
+            Self::LinearDrag(force) => Box::new(force),
+            Self::GravityZone(force) => Box::new(force),
+        }
+    }
+}
+
+/// Same acceleration for every object, regardless of its mass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UniformGravity {
+    pub acceleration: Vec2,
+}
+impl UniformGravity {
+    #[must_use]
+    pub const fn new(acceleration: Vec2) -> Self {
+        Self { acceleration }
+    }
+}
+impl ForceGenerator for UniformGravity {
+    fn apply(&self, objects: &mut [VerletObject], _dt: f32) {
+        for obj in objects {
+            obj.accelerate(self.acceleration);
+        }
+    }
+
+    fn saved(&self) -> Option<SavedForce> {
+        Some(SavedForce::UniformGravity(self.clone()))
+    }
+}
+
+/// Pulls objects towards ``center`` (pushes them away for negative ``strength``) with an
+/// acceleration of ``strength / distance^falloff``.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointAttractor {
+    pub center: Vec2,
+    pub strength: f32,
+    pub falloff: f32,
+    /// Distances below this are clamped, keeps objects passing close to the center sane.
+    pub min_distance: f32,
+    /// Objects further away than this aren't affected.
+    pub range: f32,
+}
+impl PointAttractor {
+    #[must_use]
+    pub const fn new(center: Vec2, strength: f32, falloff: f32) -> Self {
+        Self {
+            center,
+            strength,
+            falloff,
+            min_distance: 0.01,
+            range: f32::INFINITY,
+        }
+    }
+
+    #[must_use]
+    pub const fn repeller(center: Vec2, strength: f32, falloff: f32) -> Self {
+        Self::new(center, -strength, falloff)
+    }
+}
+impl ForceGenerator for PointAttractor {
+    fn apply(&self, objects: &mut [VerletObject], _dt: f32) {
+        for obj in objects {
+            let delta = self.center - obj.get_center();
+            let distance = delta.magnitude();
+            if distance > self.range || distance <= f32::EPSILON {
+                continue;
+            }
+            let magnitude = self.strength / distance.max(self.min_distance).powf(self.falloff);
+            obj.accelerate(delta / distance * magnitude);
+        }
+    }
+
+    fn saved(&self) -> Option<SavedForce> {
+        Some(SavedForce::PointAttractor(self.clone()))
+    }
+}
+
+/// Slows objects down proportionally to their velocity, independent of mass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearDrag {
+    pub coefficient: f32,
+}
+impl LinearDrag {
+    #[must_use]
+    pub const fn new(coefficient: f32) -> Self {
+        Self { coefficient }
+    }
+}
+impl ForceGenerator for LinearDrag {
+    fn apply(&self, objects: &mut [VerletObject], dt: f32) {
+        for obj in objects {
+            let velocity = obj.get_displacement() / dt;
+            obj.accelerate(-velocity * self.coefficient);
+        }
+    }
+
+    fn saved(&self) -> Option<SavedForce> {
+        Some(SavedForce::LinearDrag(self.clone()))
+    }
+}
+
+/// Drags objects along a velocity field. The force grows with the object's diameter and
+/// with the difference between the wind and object velocity, so small light objects are
+/// carried away first.
+pub struct WindField {
+    velocity_at: Box<dyn Fn(Vec2) -> Vec2>,
+    pub coefficient: f32,
+}
+impl WindField {
+    pub fn new(velocity_at: impl Fn(Vec2) -> Vec2 + 'static, coefficient: f32) -> Self {
+        Self {
+            velocity_at: Box::new(velocity_at),
+            coefficient,
+        }
+    }
+
+    #[must_use]
+    pub fn uniform(velocity: Vec2, coefficient: f32) -> Self {
+        Self::new(move |_| velocity, coefficient)
+    }
+}
+impl ForceGenerator for WindField {
+    fn apply(&self, objects: &mut [VerletObject], dt: f32) {
+        for obj in objects {
+            let relative_velocity =
+                (self.velocity_at)(obj.get_center()) - obj.get_displacement() / dt;
+            let force = relative_velocity * (self.coefficient * 2.0 * obj.get_radius());
+            obj.accelerate(force * obj.get_inverse_mass());
+        }
+    }
+}
+
+/// Extra acceleration for objects whose centers are inside ``region``.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GravityZone {
+    pub region: Container,
+    pub acceleration: Vec2,
+}
+impl GravityZone {
+    #[must_use]
+    pub const fn new(region: Container, acceleration: Vec2) -> Self {
+        Self {
+            region,
+            acceleration,
+        }
+    }
+}
+impl ForceGenerator for GravityZone {
+    fn apply(&self, objects: &mut [VerletObject], _dt: f32) {
+        objects
+            .iter_mut()
+            .filter(|obj| self.region.signed_distance(obj.get_center()).0 <= 0.0)
+            .for_each(|obj| obj.accelerate(self.acceleration));
+    }
+
+    fn saved(&self) -> Option<SavedForce> {
+        Some(SavedForce::GravityZone(self.clone()))
+    }
+}
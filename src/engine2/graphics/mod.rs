@@ -4,22 +4,49 @@ mod vertex;
 
 use glium::{
     glutin::{dpi::PhysicalSize, event_loop::EventLoop, window::WindowBuilder, ContextBuilder},
-    index::PrimitiveType,
-    uniform, Display, DrawParameters, IndexBuffer, Program, Surface, VertexBuffer,
+    index::{NoIndices, PrimitiveType},
+    uniform,
+    uniforms::EmptyUniforms,
+    Blend, Display, DrawParameters, IndexBuffer, Program, Surface, VertexBuffer,
 };
 
+use nalgebra_glm::Vec2;
+
 use crate::engine2::{
-    graphics::{shape::Shape, vertex::Vertex},
+    graphics::{
+        shape::Shape,
+        vertex::{Instance, MeshVertex, QuadVertex, Vertex},
+    },
     verlet_object::VerletObject,
 };
 
+// corners of the quad every object is drawn with, in triangle strip order
+const QUAD_CORNERS: [[f32; 2]; 4] = [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]];
+// the same quad as a triangle list
+const QUAD_INDICES: [u32; 6] = [0, 1, 2, 2, 1, 3];
+
 pub struct Renderer {
     pub display: Display,
     background_color: [f32; 4],
     // pub default_shape: Shape,
     program: Program,
-    vertex_buffer: Option<VertexBuffer<Vertex>>,
-    index_buffer: Option<IndexBuffer<u16>>,
+    circle_program: Program,
+    // unit quad shared by all objects, the fragment shader cuts the disc out of it
+    quad: VertexBuffer<MeshVertex>,
+    outline_width: f32,
+    outline_color: [f32; 4],
+    // one record per object
+    instance_buffer: Option<VertexBuffer<Instance>>,
+    // without instancing every object gets its own four vertices, indexed with u32 so any
+    // number of objects fits
+    instancing: bool,
+    quad_vertices: Option<VertexBuffer<QuadVertex>>,
+    quad_indices: Option<IndexBuffer<u32>>,
+    capacity: usize,
+    drawn_objects: usize,
+    // filled polygons, drawn behind the objects as plain triangles
+    polygon_buffer: Option<VertexBuffer<Vertex>>,
+    polygon_vertices: usize,
 }
 impl Renderer {
     pub fn new<T>(event_loop: &EventLoop<T>, width: u32, height: u32) -> Self {
@@ -32,13 +59,34 @@ impl Renderer {
 
         let program = Program::from_source(&display, shaders::VERTEX, shaders::FRAGMENT, None)
             .expect("Program creation error.");
+        let circle_program = Program::from_source(
+            &display,
+            shaders::CIRCLE_VERTEX,
+            shaders::CIRCLE_FRAGMENT,
+            None,
+        )
+        .expect("Program creation error.");
+
+        let quad_corners = QUAD_CORNERS.map(|position| MeshVertex { position });
+        let quad = VertexBuffer::new(&display, &quad_corners).expect("Unable to create the quad.");
+        let instancing = quad.per_instance().is_ok();
         Self {
             display,
             background_color: [0.0, 0.0, 0.0, 1.0],
             // default_shape: Shape::circle(RADIUS, [1.0, 1.0, 1.0, 1.0]),
             program,
-            vertex_buffer: None,
-            index_buffer: None,
+            circle_program,
+            quad,
+            outline_width: 0.0,
+            outline_color: [0.0, 0.0, 0.0, 1.0],
+            instance_buffer: None,
+            instancing,
+            quad_vertices: None,
+            quad_indices: None,
+            capacity: 0,
+            drawn_objects: 0,
+            polygon_buffer: None,
+            polygon_vertices: 0,
         }
     }
 
@@ -50,93 +98,249 @@ impl Renderer {
             self.background_color[2],
             self.background_color[3],
         );
-        if let Some(vb) = &self.vertex_buffer {
-            if let Some(ib) = &self.index_buffer {
-                frame
-                    .draw(
-                        vb,
-                        ib,
-                        &self.program,
-                        &uniform! {
-                            u_color: [1.0f32, 0.0, 1.0, 1.0],
-                        },
-                        &DrawParameters::default(),
-                    )
-                    .expect("Unable to draw this obj.");
-            };
+        if let Some(polygons) = self
+            .polygon_buffer
+            .as_ref()
+            .and_then(|pb| pb.slice(0..self.polygon_vertices))
+        {
+            frame
+                .draw(
+                    polygons,
+                    NoIndices(PrimitiveType::TrianglesList),
+                    &self.program,
+                    &EmptyUniforms,
+                    &DrawParameters::default(),
+                )
+                .expect("Unable to draw polygons.");
+        }
+        let (width, height) = frame.get_dimensions();
+        #[allow(clippy::cast_precision_loss)]
+        let pixel = [2.0 / width.max(1) as f32, 2.0 / height.max(1) as f32];
+        let uniforms = uniform! {
+            u_pixel: pixel,
+            u_color: self.outline_color,
+            u_outline_width: self.outline_width,
+        };
+        let draw_parameters = DrawParameters {
+            blend: Blend::alpha_blending(),
+            ..DrawParameters::default()
+        };
+        if let Some(instances) = self
+            .instance_buffer
+            .as_ref()
+            .and_then(|ib| ib.slice(0..self.drawn_objects))
+        {
+            frame
+                .draw(
+                    (
+                        &self.quad,
+                        instances
+                            .per_instance()
+                            .expect("Instancing was available when the renderer was created."),
+                    ),
+                    NoIndices(PrimitiveType::TriangleStrip),
+                    &self.circle_program,
+                    &uniforms,
+                    &draw_parameters,
+                )
+                .expect("Unable to draw objects.");
+        }
+        if let (Some(vb), Some(ib)) = (
+            &self.quad_vertices,
+            self.quad_indices
+                .as_ref()
+                .and_then(|ib| ib.slice(0..self.drawn_objects * QUAD_INDICES.len())),
+        ) {
+            frame
+                .draw(vb, ib, &self.circle_program, &uniforms, &draw_parameters)
+                .expect("Unable to draw objects.");
         }
         frame.finish().expect("Unable to finish drawing a frame.");
     }
 
-    pub fn rewrite_vertex_buffer(&mut self, objects: &Vec<VerletObject>) {
-        let mut vertices: Vec<Vertex> = Vec::new();
-        for obj in objects {
-            for vertex_position in Shape::circle(obj.get_radius(), [1.0, 1.0, 1.0, 1.0]).vertices {
-                let translated_vertex_position = vertex_position + obj.get_center();
-                vertices.push(Vertex {
-                    position: translated_vertex_position.into(),
-                    color: obj.get_color(),
-                });
+    /// Draws a ``width`` pixels wide ring of ``color`` along the edge of every object, a
+    /// width of zero turns the outline off.
+    pub const fn set_outline(&mut self, width: f32, color: [f32; 4]) {
+        self.outline_width = width.max(0.0);
+        self.outline_color = color;
+    }
+
+    /// Makes room for ``objects_number`` objects. Buffers are only reallocated when they
+    /// have to grow, otherwise this just changes how many objects get drawn.
+    pub fn resize(&mut self, objects_number: usize) {
+        let allocated = self.instance_buffer.is_some() || self.quad_vertices.is_some();
+        if objects_number > self.capacity || !allocated {
+            let capacity = objects_number.next_power_of_two();
+            if self.instancing {
+                self.instance_buffer = Some(
+                    VertexBuffer::empty_dynamic(&self.display, capacity)
+                        .expect("Function resize() failed to create instance buffer."),
+                );
+            } else {
+                self.quad_vertices = Some(
+                    VertexBuffer::empty_dynamic(&self.display, capacity * QUAD_CORNERS.len())
+                        .expect("Function resize() failed to create vertex buffer."),
+                );
+                self.quad_indices = Some(
+                    IndexBuffer::new(
+                        &self.display,
+                        PrimitiveType::TrianglesList,
+                        &quad_indices(capacity),
+                    )
+                    .expect("Function resize() failed to create index buffer."),
+                );
             }
+            self.capacity = capacity;
         }
-        self.vertex_buffer = Some(
-            VertexBuffer::new(&self.display, &vertices)
-                .expect("Function rewrite_vertex_buffer() failed to create buffer."),
-        );
+        self.drawn_objects = objects_number;
     }
 
-    pub fn rewrite_index_buffer(&mut self, objects: &Vec<VerletObject>) {
-        let mut indices: Vec<u16> = Vec::new();
-        let entities = objects.len();
-        for obj_nr in 0..entities {
-            #[allow(clippy::cast_possible_truncation)]
-            indices.extend_from_slice(
-                &shape::CIRCLE_INDICES
-                    .as_slice()
-                    .iter()
-                    .map(|v_idx| v_idx + obj_nr as u16 * shape::VERTICES_OF_A_CIRCLE)
-                    .collect::<Vec<u16>>(),
-            );
+    /// Writes the center, radius and color of every object straight into the instance
+    /// buffer, or into all four corners of its quad without instancing. ``alpha`` blends
+    /// every object between its position at the start of the tick (``tick_start``) and its
+    /// current one, see ``SimulationClock::alpha``.
+    pub fn update_vertex_buffer(
+        &mut self,
+        objects: &[VerletObject],
+        tick_start: &[Vec2],
+        alpha: f32,
+    ) {
+        if objects.is_empty() {
+            return;
         }
-        self.index_buffer = Some(
-            IndexBuffer::new(
-                &self.display,
-                PrimitiveType::TrianglesList,
-                indices.as_slice(),
-            )
-            .expect("Function rewrite_index_buffer() failed to create buffer."),
-        );
-    }
+        let instance = |obj: &VerletObject, start: &Vec2| Instance {
+            center: start.lerp(&obj.get_center(), alpha).into(),
+            radius: obj.get_radius(),
+            color: obj.get_color(),
+        };
 
-    pub fn update_vertex_buffer(&mut self, objects: &Vec<VerletObject>) {
-        let mut vertices: Vec<Vertex> = Vec::new();
-        for obj in objects {
-            for vertex_position in Shape::circle(obj.get_radius(), [1.0, 1.0, 1.0, 1.0]).vertices {
-                let translated_vertex_position = vertex_position + obj.get_center();
-                vertices.push(Vertex {
-                    position: translated_vertex_position.into(),
-                    color: obj.get_color(),
-                });
+        if let Some(slice) = self
+            .instance_buffer
+            .as_mut()
+            .and_then(|ib| ib.slice_mut(0..objects.len()))
+        {
+            let mut mapping = slice.map_write();
+            for (obj_idx, (obj, start)) in objects.iter().zip(tick_start).enumerate() {
+                mapping.set(obj_idx, instance(obj, start));
+            }
+        }
+        if let Some(slice) = self
+            .quad_vertices
+            .as_mut()
+            .and_then(|vb| vb.slice_mut(0..objects.len() * QUAD_CORNERS.len()))
+        {
+            let mut mapping = slice.map_write();
+            for (obj_idx, (obj, start)) in objects.iter().zip(tick_start).enumerate() {
+                let Instance {
+                    center,
+                    radius,
+                    color,
+                } = instance(obj, start);
+                for (corner_idx, position) in QUAD_CORNERS.into_iter().enumerate() {
+                    mapping.set(
+                        obj_idx * QUAD_CORNERS.len() + corner_idx,
+                        QuadVertex {
+                            position,
+                            center,
+                            radius,
+                            color,
+                        },
+                    );
+                }
             }
         }
-        if let Some(vertex_buffer) = &self.vertex_buffer {
-            vertex_buffer.write(&vertices);
+    }
+
+    /// Replaces the filled polygons drawn behind the objects. Polygons are triangulated as
+    /// a fan around their centroid, so they have to be star-shaped around it.
+    pub fn update_polygons(&mut self, polygons: &[Shape]) {
+        let vertices = Self::polygon_vertices(polygons);
+        if vertices.len() > self.polygon_buffer.as_ref().map_or(0, |pb| pb.len()) {
+            self.polygon_buffer = Some(
+                VertexBuffer::empty_dynamic(&self.display, vertices.len().next_power_of_two())
+                    .expect("Function update_polygons() failed to create vertex buffer."),
+            );
         }
+        if let Some(slice) = self
+            .polygon_buffer
+            .as_ref()
+            .and_then(|pb| pb.slice(0..vertices.len()))
+        {
+            slice.write(&vertices);
+        }
+        self.polygon_vertices = vertices.len();
     }
 
-    // I'm not sure if this is going to be useful in any forseeable future
-    pub fn update_index_buffer(&mut self, objects: &Vec<VerletObject>) {
-        let mut indices: Vec<u16> = Vec::new();
-        let entities = objects.len();
-        for obj_nr in 0..entities {
-            for index in shape::CIRCLE_INDICES {
-                #[allow(clippy::cast_possible_truncation)]
-                indices.push(index + obj_nr as u16 * shape::VERTICES_OF_A_CIRCLE);
+    #[allow(clippy::cast_precision_loss)]
+    fn polygon_vertices(polygons: &[Shape]) -> Vec<Vertex> {
+        let mut vertices: Vec<Vertex> = Vec::new();
+        for polygon in polygons
+            .iter()
+            .filter(|polygon| polygon.vertices.len() >= 3)
+        {
+            let centroid = polygon
+                .vertices
+                .iter()
+                .fold(Vec2::zeros(), |sum, vertex| sum + vertex)
+                / polygon.vertices.len() as f32;
+            let vertex = |position: Vec2| Vertex {
+                position: position.into(),
+                color: polygon.color,
+            };
+            for (vertex_idx, &position) in polygon.vertices.iter().enumerate() {
+                let next = polygon.vertices[(vertex_idx + 1) % polygon.vertices.len()];
+                vertices.extend([vertex(centroid), vertex(position), vertex(next)]);
             }
         }
+        vertices
+    }
+}
+
+/// Triangle list indices of ``objects_number`` quads stored as four consecutive vertices
+/// each.
+///
+/// # Panics
+/// If the vertices can't be indexed with ``u32``.
+fn quad_indices(objects_number: usize) -> Vec<u32> {
+    let vertices = objects_number * QUAD_CORNERS.len();
+    assert!(
+        u32::try_from(vertices).is_ok(),
+        "{objects_number} objects can't be indexed with u32."
+    );
+    let mut indices = Vec::with_capacity(objects_number * QUAD_INDICES.len());
+    for first_vertex in (0..vertices).step_by(QUAD_CORNERS.len()) {
+        #[allow(clippy::cast_possible_truncation)]
+        let first_vertex = first_vertex as u32;
+        indices.extend(QUAD_INDICES.map(|corner| first_vertex + corner));
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{quad_indices, QUAD_CORNERS, QUAD_INDICES};
+
+    #[test]
+    fn quad_indices_stay_within_their_quad() {
+        let indices = quad_indices(3);
+        assert_eq!(
+            indices,
+            [0, 1, 2, 2, 1, 3, 4, 5, 6, 6, 5, 7, 8, 9, 10, 10, 9, 11]
+        );
+    }
+
+    #[test]
+    fn quad_indices_go_past_u16() {
+        let objects_number = 2_000_000;
+        let indices = quad_indices(objects_number);
+        assert_eq!(indices.len(), objects_number * QUAD_INDICES.len());
 
-        if let Some(index_buffer) = &self.index_buffer {
-            index_buffer.write(indices.as_slice());
+        for (obj_idx, quad) in indices.chunks_exact(QUAD_INDICES.len()).enumerate() {
+            let first_vertex = u32::try_from(obj_idx * QUAD_CORNERS.len()).unwrap();
+            let expected = QUAD_INDICES.map(|corner| first_vertex + corner);
+            assert_eq!(quad, expected, "quad of object {obj_idx}");
         }
+        let last_vertex = u32::try_from(objects_number * QUAD_CORNERS.len() - 1).unwrap();
+        assert_eq!(indices.iter().max(), Some(&last_vertex));
     }
 }
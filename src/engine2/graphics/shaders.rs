@@ -1,4 +1,4 @@
-pub const VERTEX: &str = r#"
+pub const VERTEX: &str = r"
 #version 150
 
 in vec2 position;
@@ -10,12 +10,66 @@ void main() {
     gl_Position = vec4(position, 0.0, 1.0);
     v_color = color;
 }
-"#;
+";
 
-pub const FRAGMENT: &str = r#"
+/// Scales and moves the unit quad per instance, ``v_local`` is 1 at the edge of the disc.
+/// The quad reaches one pixel (``u_pixel``, in clip space units) past the edge so the
+/// fragment shader's fade isn't cut off.
+pub const CIRCLE_VERTEX: &str = r"
+#version 150
+
+uniform vec2 u_pixel;
+
+in vec2 position;
+in vec2 center;
+in float radius;
+in vec4 color;
+
+out vec2 v_local;
+out vec4 v_color;
+
+void main() {
+    vec2 local = position * (1.0 + max(u_pixel.x, u_pixel.y) / max(radius, 1e-6));
+    gl_Position = vec4(center + local * radius, 0.0, 1.0);
+    v_local = local;
+    v_color = color;
+}
+";
+
+/// Cuts a disc out of the quad by its distance to the center. Edges fade over one pixel
+/// whatever the size of the disc, ``u_outline_width`` pixels along the edge get
+/// ``u_color``.
+pub const CIRCLE_FRAGMENT: &str = r"
 #version 150
 
 uniform vec4 u_color;
+uniform float u_outline_width;
+
+in vec2 v_local;
+in vec4 v_color;
+out vec4 color;
+
+void main() {
+    float center_distance = length(v_local);
+    // quad units per pixel
+    float pixel = max(fwidth(center_distance), 1e-6);
+    float inside = 1.0 - center_distance;
+
+    float coverage = clamp(inside / pixel + 0.5, 0.0, 1.0);
+    if (coverage <= 0.0) {
+        discard;
+    }
+    float outline = 0.0;
+    if (u_outline_width > 0.0) {
+        outline = clamp(u_outline_width + 0.5 - inside / pixel, 0.0, 1.0);
+    }
+    vec4 fill = mix(v_color, u_color, outline);
+    color = vec4(fill.rgb, fill.a * coverage);
+}
+";
+
+pub const FRAGMENT: &str = r"
+#version 150
 
 in vec4 v_color;
 out vec4 color;
@@ -23,4 +77,4 @@ out vec4 color;
 void main() {
     color = v_color;
 }
-"#;
+";
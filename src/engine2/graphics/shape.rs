@@ -46,7 +46,7 @@ impl Shape {
     }
 
     pub fn circle(radius: f32, color: [f32; 4]) -> Self {
-        let interior_angle = TAU / VERTICES_OF_A_CIRCLE as f32;
+        let interior_angle = TAU / f32::from(VERTICES_OF_A_CIRCLE);
 
         let mut vertices: Vec<Vec2> = Vec::new();
 
@@ -6,3 +6,31 @@ pub struct Vertex {
     pub color: [f32; 4],
 }
 implement_vertex!(Vertex, position, color);
+
+/// Vertex of the unit quad every object is drawn with.
+#[derive(Clone, Copy, Debug)]
+pub struct MeshVertex {
+    pub position: [f32; 2],
+}
+implement_vertex!(MeshVertex, position);
+
+/// Per-object attributes of an instanced circle, the vertex shader scales the mesh by
+/// ``radius`` and moves it to ``center``.
+#[derive(Clone, Copy, Debug)]
+pub struct Instance {
+    pub center: [f32; 2],
+    pub radius: f32,
+    pub color: [f32; 4],
+}
+implement_vertex!(Instance, center, radius, color);
+
+/// Corner of an object's quad with the object's attributes copied in, used where
+/// instancing isn't available.
+#[derive(Clone, Copy, Debug)]
+pub struct QuadVertex {
+    pub position: [f32; 2],
+    pub center: [f32; 2],
+    pub radius: f32,
+    pub color: [f32; 4],
+}
+implement_vertex!(QuadVertex, position, center, radius, color);
@@ -0,0 +1,102 @@
+use std::{error::Error, fmt};
+
+/// Stable reference to an object of a ``World``.
+///
+/// Indices into ``World::objects`` change when objects are removed, handles don't. Once
+/// the object is removed its handle goes stale and every access through it fails, even if
+/// the slot gets reused by a new object.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectHandle {
+    slot: u32,
+    generation: u32,
+}
+impl ObjectHandle {
+    #[must_use]
+    pub const fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+impl fmt::Display for ObjectHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}v{}", self.slot, self.generation)
+    }
+}
+
+/// Returned when a handle refers to an object that has been removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaleHandle(pub ObjectHandle);
+impl fmt::Display for StaleHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "object handle {} is stale, the object was removed",
+            self.0
+        )
+    }
+}
+impl Error for StaleHandle {}
+
+struct Slot {
+    generation: u32,
+    object_idx: Option<usize>,
+}
+
+/// Maps handles to the current indices of objects, has to be told about every insertion
+/// and removal.
+#[derive(Default)]
+pub(crate) struct HandleMap {
+    slots: Vec<Slot>,
+    free_slots: Vec<u32>,
+    // slot of every object, by object index
+    slot_of: Vec<u32>,
+}
+impl HandleMap {
+    /// Registers an object pushed at the end of the objects list.
+    pub fn push(&mut self) -> ObjectHandle {
+        let object_idx = self.slot_of.len();
+        let slot = if let Some(slot) = self.free_slots.pop() {
+            self.slots[slot as usize].object_idx = Some(object_idx);
+            slot
+        } else {
+            self.slots.push(Slot {
+                generation: 0,
+                object_idx: Some(object_idx),
+            });
+            u32::try_from(self.slots.len() - 1).expect("Too many object slots.")
+        };
+        self.slot_of.push(slot);
+        ObjectHandle {
+            slot,
+            generation: self.slots[slot as usize].generation,
+        }
+    }
+
+    /// Mirrors ``objects.swap_remove(removed)``.
+    pub fn swap_remove(&mut self, removed: usize) {
+        let slot = self.slot_of.swap_remove(removed);
+        let freed = &mut self.slots[slot as usize];
+        freed.object_idx = None;
+        freed.generation = freed.generation.wrapping_add(1);
+        self.free_slots.push(slot);
+
+        if let Some(&moved_slot) = self.slot_of.get(removed) {
+            self.slots[moved_slot as usize].object_idx = Some(removed);
+        }
+    }
+
+    pub fn index_of(&self, handle: ObjectHandle) -> Result<usize, StaleHandle> {
+        self.slots
+            .get(handle.slot as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.object_idx)
+            .ok_or(StaleHandle(handle))
+    }
+
+    /// Handle of the object currently at ``object_idx``.
+    pub fn handle_at(&self, object_idx: usize) -> Option<ObjectHandle> {
+        self.slot_of.get(object_idx).map(|&slot| ObjectHandle {
+            slot,
+            generation: self.slots[slot as usize].generation,
+        })
+    }
+}
@@ -0,0 +1,168 @@
+use crate::engine2::{
+    collision::{correction_weights, pair_mut},
+    verlet_object::VerletObject,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LinkKind {
+    /// Keeps the objects exactly ``rest_length`` apart (stick).
+    Rigid,
+    /// Pulls the objects towards ``rest_length`` with a damped spring force.
+    Spring { stiffness: f32, damping: f32 },
+}
+
+/// Connection between two objects, referenced by their index in the ``World``.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Link {
+    pub a: usize,
+    pub b: usize,
+    pub rest_length: f32,
+    pub kind: LinkKind,
+}
+impl Link {
+    #[must_use]
+    pub const fn rigid(a: usize, b: usize, rest_length: f32) -> Self {
+        Self {
+            a,
+            b,
+            rest_length,
+            kind: LinkKind::Rigid,
+        }
+    }
+
+    #[must_use]
+    pub const fn spring(
+        a: usize,
+        b: usize,
+        rest_length: f32,
+        stiffness: f32,
+        damping: f32,
+    ) -> Self {
+        Self {
+            a,
+            b,
+            rest_length,
+            kind: LinkKind::Spring { stiffness, damping },
+        }
+    }
+}
+
+/// Identifies a link for as long as it exists, removing other links doesn't change it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LinkId(u64);
+
+/// All links of a ``World``. Springs add to the objects' acceleration before they are
+/// integrated, rigid links are projected after collisions every substep.
+#[derive(Default)]
+pub struct Links {
+    entries: Vec<Link>,
+    // id of the link at the same index, ids are never reused
+    ids: Vec<LinkId>,
+    next_id: u64,
+}
+impl Links {
+    pub(crate) fn add(&mut self, link: Link) -> LinkId {
+        let id = LinkId(self.next_id);
+        self.next_id += 1;
+        self.entries.push(link);
+        self.ids.push(id);
+        id
+    }
+
+    /// Removes the link keeping the order of the others, ``None`` if it's already gone.
+    pub(crate) fn remove(&mut self, id: LinkId) -> Option<Link> {
+        let link_idx = self.ids.iter().position(|&other| other == id)?;
+        self.ids.remove(link_idx);
+        Some(self.entries.remove(link_idx))
+    }
+
+    #[must_use]
+    pub fn get(&self, id: LinkId) -> Option<&Link> {
+        let link_idx = self.ids.iter().position(|&other| other == id)?;
+        Some(&self.entries[link_idx])
+    }
+
+    /// Links together with their ids, in the order they are solved.
+    pub fn iter(&self) -> impl Iterator<Item = (LinkId, &Link)> {
+        self.ids.iter().copied().zip(&self.entries)
+    }
+
+    /// Keeps links valid after ``objects.swap_remove(removed)``: links of the removed
+    /// object are dropped and the ones of the object moved from ``last`` are redirected.
+    pub(crate) fn on_swap_remove(&mut self, removed: usize, last: usize) {
+        let mut link_idx = 0;
+        while link_idx < self.entries.len() {
+            let link = &mut self.entries[link_idx];
+            if link.a == removed || link.b == removed {
+                self.entries.remove(link_idx);
+                self.ids.remove(link_idx);
+                continue;
+            }
+            if link.a == last {
+                link.a = removed;
+            }
+            if link.b == last {
+                link.b = removed;
+            }
+            link_idx += 1;
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.ids.clear();
+    }
+
+    #[must_use]
+    pub fn as_slice(&self) -> &[Link] {
+        &self.entries
+    }
+
+    pub fn apply_springs(&self, objects: &mut [VerletObject], dt: f32) {
+        for link in &self.entries {
+            let LinkKind::Spring { stiffness, damping } = link.kind else {
+                continue;
+            };
+            let (obj_a, obj_b) = pair_mut(objects, link.a, link.b);
+
+            let delta = obj_b.get_center() - obj_a.get_center();
+            let distance = delta.magnitude();
+            if distance <= f32::EPSILON {
+                continue;
+            }
+            let direction = delta / distance;
+
+            let relative_velocity = (obj_b.get_displacement() - obj_a.get_displacement()) / dt;
+            let magnitude = stiffness.mul_add(
+                distance - link.rest_length,
+                damping * relative_velocity.dot(&direction),
+            );
+            let force = direction * magnitude;
+
+            obj_a.accelerate(force * obj_a.get_inverse_mass());
+            obj_b.accelerate(-force * obj_b.get_inverse_mass());
+        }
+    }
+
+    pub fn solve_rigid(&self, objects: &mut [VerletObject]) {
+        for link in &self.entries {
+            if link.kind != LinkKind::Rigid {
+                continue;
+            }
+            let (obj_a, obj_b) = pair_mut(objects, link.a, link.b);
+
+            let delta = obj_b.get_center() - obj_a.get_center();
+            let distance = delta.magnitude();
+            if distance <= f32::EPSILON {
+                continue;
+            }
+            let Some((w_a, w_b)) = correction_weights(obj_a, obj_b) else {
+                continue;
+            };
+            let correction = delta * ((distance - link.rest_length) / distance);
+
+            obj_a.shift(correction * w_a);
+            obj_b.shift(-correction * w_b);
+        }
+    }
+}
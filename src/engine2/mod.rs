@@ -1,4 +1,21 @@
+pub mod aabb;
+pub mod broad_phase;
+pub mod clock;
+mod collision;
+pub mod contacts;
+pub mod container;
+pub mod emitter;
+pub mod forces;
 mod graphics;
+pub mod handle;
+pub mod links;
 pub mod objects_generator;
-mod verlet_object;
+pub mod parallel_solver;
+pub mod query;
+pub mod radius_distribution;
+pub mod raster;
+pub mod replay;
+pub mod snapshot;
+pub mod soft_body;
+pub mod verlet_object;
 pub mod world;
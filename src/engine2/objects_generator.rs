@@ -1,32 +1,479 @@
-use std::f32::consts::TAU;
+use std::f32::consts::{SQRT_2, TAU};
 
-use nalgebra_glm::Vec2;
-use rand::random;
+use nalgebra_glm::{rotation2d, vec2, vec2_to_vec3, vec3, Vec2};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
+use crate::engine2::radius_distribution::RadiusDistribution;
+
+// candidates tried around a point before Poisson-disk sampling gives up on it
+const POISSON_ATTEMPTS: usize = 30;
+
+/// Arrangement of the generated objects. All lattices are rotated by ``grid_rotation``
+/// around ``grid_center`` and their neighbouring centers are ``ObjectsGenerator::spacing``
+/// apart.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Layout {
+    /// ``grid_columns`` by ``grid_rows`` square lattice.
+    Grid,
+    /// ``grid_columns`` by ``grid_rows`` hexagonal close packing, every other row is
+    /// shifted by half the spacing.
+    Hexagonal,
+    /// Hexagonally packed disc around ``grid_center``.
+    Disc { radius: f32 },
+    /// Hexagonally packed ring around ``grid_center``.
+    Annulus {
+        inner_radius: f32,
+        outer_radius: f32,
+    },
+    /// Random centers inside a ``width`` by ``height`` rectangle around ``grid_center``, no
+    /// two of them closer than the spacing. Stops after ``max_count`` objects or when no
+    /// more fit.
+    PoissonDisk {
+        width: f32,
+        height: f32,
+        max_count: usize,
+    },
+    /// Hexagonal packing clipped to a simple, possibly concave, polygon given in world
+    /// coordinates.
+    Polygon { vertices: Vec<Vec2> },
+}
+
+/// Places objects in one of the ``Layout``s. Every random value is drawn from the
+/// generator's own RNG, so two generators with the same seed and parameters produce the
+/// same scene.
 pub struct ObjectsGenerator {
     pub grid_center: Vec2,
     pub grid_columns: usize,
     pub grid_rows: usize,
     pub grid_rotation: f32,
-    pub obj_radius: f32,
-    pub obj_radius_deviation: f32,
+    pub radius_distribution: RadiusDistribution,
     pub obj_min_separation: f32,
+    pub layout: Layout,
+    /// Objects are moved by up to this distance along each axis from their spawn point.
+    pub spawn_jitter: f32,
+    seed: u64,
+    rng: StdRng,
+}
+impl Default for ObjectsGenerator {
+    /// Seeded from system entropy, see ``seed`` to reproduce the run.
+    fn default() -> Self {
+        Self::with_seed(rand::random())
+    }
 }
 impl ObjectsGenerator {
-    pub fn default() -> Self {
+    #[must_use]
+    pub fn with_seed(seed: u64) -> Self {
         Self {
             grid_center: Vec2::new(0.0, 0.0),
             grid_columns: 50,
             grid_rows: 80,
             grid_rotation: TAU / 45.0,
-            obj_radius: 0.003,
-            obj_radius_deviation: 0.0015,
+            radius_distribution: RadiusDistribution::default(),
             obj_min_separation: 0.0003,
+            layout: Layout::Grid,
+            spawn_jitter: 0.0,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
         }
     }
+
+    #[must_use]
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restarts the random sequence from ``seed``.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// The generator's RNG, for randomness not covered by the methods below.
+    pub const fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
     pub fn random_radius(&mut self) -> f32 {
-        let randomizer = random::<f32>().mul_add(2.0, -1.0);
-        let delta_radius = randomizer * self.obj_radius_deviation;
-        self.obj_radius + delta_radius
+        self.radius_distribution.sample(&mut self.rng)
+    }
+
+    /// ``point`` moved randomly by up to ``spawn_jitter`` along each axis.
+    pub fn jittered(&mut self, point: Vec2) -> Vec2 {
+        if self.spawn_jitter <= 0.0 {
+            return point;
+        }
+        let jitter = self.spawn_jitter;
+        point
+            + vec2(
+                self.rng.gen_range(-jitter..=jitter),
+                self.rng.gen_range(-jitter..=jitter),
+            )
+    }
+
+    /// Distance between neighbouring centers, objects of the largest planned radius (see
+    /// ``RadiusDistribution::upper_bound``) are then ``obj_min_separation`` apart.
+    #[must_use]
+    pub fn spacing(&self) -> f32 {
+        self.radius_distribution
+            .upper_bound()
+            .mul_add(2.0, self.obj_min_separation)
+    }
+
+    /// Centers of the objects to spawn, see ``World::populate``.
+    pub fn positions(&mut self) -> Vec<Vec2> {
+        let max_radius = self.radius_distribution.upper_bound();
+        match self.layout.clone() {
+            Layout::Grid => self.grid_positions(),
+            Layout::Hexagonal => self.hexagonal_positions(),
+            Layout::Disc { radius } => self.ring_positions(0.0, radius - max_radius),
+            Layout::Annulus {
+                inner_radius,
+                outer_radius,
+            } => self.ring_positions(inner_radius + max_radius, outer_radius - max_radius),
+            Layout::PoissonDisk {
+                width,
+                height,
+                max_count,
+            } => self.poisson_disk_positions(width, height, max_count),
+            Layout::Polygon { vertices } => self.polygon_positions(&vertices, max_radius),
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn grid_positions(&self) -> Vec<Vec2> {
+        let distance = self.spacing();
+        let x = -(self.grid_columns as f32 / 2.0) * distance;
+        let mut y = (self.grid_rows as f32 / 2.0) * distance;
+
+        let mut positions = Vec::with_capacity(self.grid_rows * self.grid_columns);
+        for _row in 0..self.grid_rows {
+            for col in 0..self.grid_columns {
+                let temp_x = distance.mul_add(col as f32, x);
+                positions.push(self.to_world(vec2(temp_x, y)));
+            }
+            y -= distance;
+        }
+        positions
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn hexagonal_positions(&self) -> Vec<Vec2> {
+        let distance = self.spacing();
+        let row_distance = distance * 3.0_f32.sqrt() / 2.0;
+        let x = -(self.grid_columns as f32 / 2.0) * distance;
+        let y = (self.grid_rows as f32 / 2.0) * row_distance;
+
+        let mut positions = Vec::with_capacity(self.grid_rows * self.grid_columns);
+        for row in 0..self.grid_rows {
+            let row_x = if row % 2 == 1 { x + distance / 2.0 } else { x };
+            for col in 0..self.grid_columns {
+                let local = vec2(
+                    distance.mul_add(col as f32, row_x),
+                    row_distance.mul_add(-(row as f32), y),
+                );
+                positions.push(self.to_world(local));
+            }
+        }
+        positions
+    }
+
+    /// Hexagonal lattice centers at most ``max_distance`` from ``grid_center`` and at least
+    /// ``min_distance`` away from it.
+    fn ring_positions(&self, min_distance: f32, max_distance: f32) -> Vec<Vec2> {
+        if max_distance < min_distance.max(0.0) {
+            return Vec::new();
+        }
+        let extent = Vec2::repeat(max_distance);
+        self.hex_lattice(-extent, extent)
+            .into_iter()
+            .filter(|local| (min_distance..=max_distance).contains(&local.magnitude()))
+            .map(|local| self.to_world(local))
+            .collect()
+    }
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn poisson_disk_positions(&mut self, width: f32, height: f32, max_count: usize) -> Vec<Vec2> {
+        let spacing = self.spacing();
+        if max_count == 0 || width <= 0.0 || height <= 0.0 || spacing <= 0.0 {
+            return Vec::new();
+        }
+        let half_size = vec2(width, height) / 2.0;
+
+        // background grid (Bridson) with cells small enough to hold a single center each
+        let cell_size = spacing / SQRT_2;
+        let cols = (width / cell_size).ceil().max(1.0) as usize;
+        let rows = (height / cell_size).ceil().max(1.0) as usize;
+        let cell_of = |point: Vec2| {
+            let col = ((point.x + half_size.x) / cell_size) as usize;
+            let row = ((point.y + half_size.y) / cell_size) as usize;
+            (col.min(cols - 1), row.min(rows - 1))
+        };
+        let mut cells: Vec<Option<usize>> = vec![None; cols * rows];
+
+        let first = vec2(
+            self.rng.gen_range(-half_size.x..=half_size.x),
+            self.rng.gen_range(-half_size.y..=half_size.y),
+        );
+        let (col, row) = cell_of(first);
+        cells[row * cols + col] = Some(0);
+        let mut points = vec![first];
+        let mut active = vec![0];
+
+        while !active.is_empty() && points.len() < max_count {
+            let active_idx = self.rng.gen_range(0..active.len());
+            let origin = points[active[active_idx]];
+
+            let mut accepted = None;
+            for _ in 0..POISSON_ATTEMPTS {
+                let angle = self.rng.gen_range(0.0..TAU);
+                let distance = self.rng.gen_range(spacing..2.0 * spacing);
+                let candidate = origin + vec2(angle.cos(), angle.sin()) * distance;
+                if candidate.x.abs() > half_size.x || candidate.y.abs() > half_size.y {
+                    continue;
+                }
+                let (col, row) = cell_of(candidate);
+                let too_close = (row.saturating_sub(2)..=(row + 2).min(rows - 1)).any(|row| {
+                    (col.saturating_sub(2)..=(col + 2).min(cols - 1)).any(|col| {
+                        cells[row * cols + col].is_some_and(|point_idx| {
+                            points[point_idx].metric_distance(&candidate) < spacing
+                        })
+                    })
+                });
+                if !too_close {
+                    cells[row * cols + col] = Some(points.len());
+                    accepted = Some(candidate);
+                    break;
+                }
+            }
+
+            match accepted {
+                Some(candidate) => {
+                    active.push(points.len());
+                    points.push(candidate);
+                }
+                None => {
+                    active.swap_remove(active_idx);
+                }
+            }
+        }
+        points
+            .into_iter()
+            .map(|local| self.to_world(local))
+            .collect()
+    }
+
+    fn polygon_positions(&self, vertices: &[Vec2], max_radius: f32) -> Vec<Vec2> {
+        if vertices.len() < 3 {
+            return Vec::new();
+        }
+        let rotation = rotation2d(-self.grid_rotation);
+        let to_local = |point: &Vec2| (rotation * vec2_to_vec3(&(point - self.grid_center))).xy();
+        let (min, max) = vertices.iter().map(to_local).fold(
+            (Vec2::repeat(f32::INFINITY), Vec2::repeat(f32::NEG_INFINITY)),
+            |(min, max), local| (min.inf(&local), max.sup(&local)),
+        );
+        self.hex_lattice(min, max)
+            .into_iter()
+            .map(|local| self.to_world(local))
+            .filter(|&point| {
+                contains_point(vertices, point) && boundary_distance(vertices, point) >= max_radius
+            })
+            .collect()
+    }
+
+    /// Hexagonal lattice in local coordinates covering the ``min``..``max`` box, aligned so
+    /// that one of its points is at the origin.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn hex_lattice(&self, min: Vec2, max: Vec2) -> Vec<Vec2> {
+        let distance = self.spacing();
+        let row_distance = distance * 3.0_f32.sqrt() / 2.0;
+        if distance <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut points = Vec::new();
+        let first_row = (min.y / row_distance).ceil() as i64;
+        let last_row = (max.y / row_distance).floor() as i64;
+        for row in first_row..=last_row {
+            let offset = if row.rem_euclid(2) == 1 {
+                distance / 2.0
+            } else {
+                0.0
+            };
+            let first_col = ((min.x - offset) / distance).ceil() as i64;
+            let last_col = ((max.x - offset) / distance).floor() as i64;
+            for col in first_col..=last_col {
+                points.push(vec2(
+                    (col as f32).mul_add(distance, offset),
+                    row as f32 * row_distance,
+                ));
+            }
+        }
+        points
+    }
+
+    fn to_world(&self, local: Vec2) -> Vec2 {
+        (rotation2d(self.grid_rotation) * vec3(local.x, local.y, 1.0)
+            + vec2_to_vec3(&self.grid_center))
+        .xy()
+    }
+}
+
+/// Even-odd rule, works for concave polygons.
+fn contains_point(vertices: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    let mut previous = vertices[vertices.len() - 1];
+    for &vertex in vertices {
+        if (vertex.y > point.y) != (previous.y > point.y) {
+            let crossing_x =
+                (previous.x - vertex.x) * (point.y - vertex.y) / (previous.y - vertex.y) + vertex.x;
+            if point.x < crossing_x {
+                inside = !inside;
+            }
+        }
+        previous = vertex;
+    }
+    inside
+}
+
+fn boundary_distance(vertices: &[Vec2], point: Vec2) -> f32 {
+    let mut previous = vertices[vertices.len() - 1];
+    let mut distance = f32::INFINITY;
+    for &vertex in vertices {
+        let edge = vertex - previous;
+        let t = ((point - previous).dot(&edge) / edge.magnitude_squared().max(f32::EPSILON))
+            .clamp(0.0, 1.0);
+        distance = distance.min(point.metric_distance(&(previous + edge * t)));
+        previous = vertex;
+    }
+    distance
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{vec2, Vec2};
+
+    use super::{boundary_distance, contains_point, Layout, ObjectsGenerator};
+    use crate::engine2::radius_distribution::RadiusDistribution;
+
+    const RADIUS: f32 = 0.01;
+    const EPSILON: f32 = 1e-5;
+
+    fn with_layout(layout: Layout) -> ObjectsGenerator {
+        let mut generator = ObjectsGenerator::with_seed(7);
+        generator.grid_center = vec2(0.3, -0.1);
+        generator.radius_distribution = RadiusDistribution::Uniform {
+            radius: RADIUS,
+            deviation: 0.0,
+        };
+        generator.obj_min_separation = 0.002;
+        generator.layout = layout;
+        generator
+    }
+
+    fn min_distance(positions: &[Vec2]) -> f32 {
+        let mut min = f32::INFINITY;
+        for (i, a) in positions.iter().enumerate() {
+            for b in &positions[i + 1..] {
+                min = min.min(a.metric_distance(b));
+            }
+        }
+        min
+    }
+
+    #[test]
+    fn hexagonal_is_close_packed() {
+        let mut generator = with_layout(Layout::Hexagonal);
+        generator.grid_columns = 6;
+        generator.grid_rows = 5;
+        let positions = generator.positions();
+        assert_eq!(positions.len(), 30);
+        assert!((min_distance(&positions) - generator.spacing()).abs() < EPSILON);
+
+        // the first center of the second row sits between the first two of the first row
+        let between = (positions[0] + positions[1]) / 2.0;
+        let row_shift = (positions[6] - between).normalize();
+        let row_direction = (positions[1] - positions[0]).normalize();
+        assert!(row_shift.dot(&row_direction).abs() < EPSILON);
+    }
+
+    #[test]
+    fn disc_stays_inside_its_radius() {
+        let radius = 0.2;
+        let mut generator = with_layout(Layout::Disc { radius });
+        let center = generator.grid_center;
+        let positions = generator.positions();
+        assert!(!positions.is_empty());
+        assert!(positions
+            .iter()
+            .all(|point| point.metric_distance(&center) <= radius - RADIUS + EPSILON));
+        assert!(min_distance(&positions) >= generator.spacing() - EPSILON);
+    }
+
+    #[test]
+    fn annulus_keeps_objects_between_its_radii() {
+        let (inner_radius, outer_radius) = (0.1, 0.2);
+        let mut generator = with_layout(Layout::Annulus {
+            inner_radius,
+            outer_radius,
+        });
+        let center = generator.grid_center;
+        let positions = generator.positions();
+        assert!(!positions.is_empty());
+        for point in &positions {
+            let distance = point.metric_distance(&center);
+            assert!(distance >= inner_radius + RADIUS - EPSILON);
+            assert!(distance <= outer_radius - RADIUS + EPSILON);
+        }
+    }
+
+    #[test]
+    fn poisson_disk_keeps_minimum_separation() {
+        let layout = Layout::PoissonDisk {
+            width: 0.4,
+            height: 0.3,
+            max_count: 500,
+        };
+        let mut generator = with_layout(layout.clone());
+        generator.grid_rotation = 0.0;
+        let center = generator.grid_center;
+        let positions = generator.positions();
+        assert!(positions.len() > 50 && positions.len() <= 500);
+        assert!(min_distance(&positions) >= generator.spacing() - EPSILON);
+        assert!(positions.iter().all(|point| {
+            let local = point - center;
+            local.x.abs() <= 0.2 + EPSILON && local.y.abs() <= 0.15 + EPSILON
+        }));
+
+        let mut same_seed = with_layout(layout);
+        same_seed.grid_rotation = 0.0;
+        assert_eq!(same_seed.positions(), positions);
+    }
+
+    #[test]
+    fn polygon_keeps_objects_inside() {
+        // L shape, concave at (0.1, 0.1)
+        let vertices = vec![
+            vec2(0.0, 0.0),
+            vec2(0.3, 0.0),
+            vec2(0.3, 0.1),
+            vec2(0.1, 0.1),
+            vec2(0.1, 0.3),
+            vec2(0.0, 0.3),
+        ];
+        let mut generator = with_layout(Layout::Polygon {
+            vertices: vertices.clone(),
+        });
+        let positions = generator.positions();
+        assert!(!positions.is_empty());
+        for &point in &positions {
+            assert!(contains_point(&vertices, point));
+            assert!(boundary_distance(&vertices, point) >= RADIUS);
+        }
+        assert!(!contains_point(&vertices, vec2(0.2, 0.2)));
     }
 }
@@ -0,0 +1,103 @@
+use std::marker::PhantomData;
+
+use rayon::prelude::*;
+
+use crate::engine2::{
+    broad_phase::uniform_grid::UniformGrid,
+    collision::{solve_collision, Penetration},
+    verlet_object::VerletObject,
+};
+
+// a stripe touches one column on each side of itself, two columns wide stripes keep the
+// same-parity stripes of a pass apart
+const MIN_STRIPE_WIDTH: usize = 2;
+
+/// Resolves collisions on a thread pool.
+///
+/// Grid columns are grouped into vertical stripes. Even stripes are solved in parallel,
+/// then odd ones. A stripe only reaches into the neighbouring column on either side, so
+/// stripes solved at the same time never share an object. The result depends on the
+/// number of threads, use the sequential path when runs have to be reproducible.
+#[derive(Default)]
+pub struct ParallelCollisionSolver {
+    grid: UniformGrid,
+}
+impl ParallelCollisionSolver {
+    /// Overlaps found are appended to ``contacts`` when it is given.
+    pub fn solve(
+        &mut self,
+        objects: &mut [VerletObject],
+        mut contacts: Option<&mut Vec<(usize, usize, Penetration)>>,
+    ) {
+        self.grid.rebuild(objects);
+        let cols = self.grid.cols();
+        if cols == 0 {
+            return;
+        }
+
+        let stripe_width = cols
+            .div_ceil(2 * rayon::current_num_threads())
+            .max(MIN_STRIPE_WIDTH);
+        let stripes = cols.div_ceil(stripe_width);
+
+        let grid = &self.grid;
+        let shared = SharedObjects::new(objects);
+        for parity in 0..2 {
+            let recording = contacts.is_some();
+            let found: Vec<Vec<(usize, usize, Penetration)>> = (parity..stripes)
+                .into_par_iter()
+                .step_by(2)
+                .map(|stripe| {
+                    let mut pairs = Vec::new();
+                    let first_col = stripe * stripe_width;
+                    for col in first_col..(first_col + stripe_width).min(cols) {
+                        for row in 0..grid.rows() {
+                            grid.cell_pairs(col, row, &mut pairs);
+                        }
+                    }
+                    let mut found = Vec::new();
+                    for (i, j) in pairs {
+                        // SAFETY: stripes of one pass touch disjoint sets of grid columns,
+                        // and every object belongs to exactly one column.
+                        let (obj1, obj2) = unsafe { shared.pair_mut(i, j) };
+                        if let Some(penetration) = solve_collision(obj1, obj2) {
+                            if recording {
+                                found.push((i, j, penetration));
+                            }
+                        }
+                    }
+                    found
+                })
+                .collect();
+            if let Some(contacts) = contacts.as_deref_mut() {
+                contacts.extend(found.into_iter().flatten());
+            }
+        }
+    }
+}
+
+struct SharedObjects<'a> {
+    ptr: *mut VerletObject,
+    len: usize,
+    _marker: PhantomData<&'a mut [VerletObject]>,
+}
+// SAFETY: access is only handed out through `pair_mut`, callers guarantee disjointness.
+unsafe impl Sync for SharedObjects<'_> {}
+
+impl<'a> SharedObjects<'a> {
+    const fn new(objects: &'a mut [VerletObject]) -> Self {
+        Self {
+            ptr: objects.as_mut_ptr(),
+            len: objects.len(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// # Safety
+    /// No other thread may access objects ``i`` and ``j`` while the references live.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn pair_mut(&self, i: usize, j: usize) -> (&mut VerletObject, &mut VerletObject) {
+        assert!(i != j && i < self.len && j < self.len);
+        (&mut *self.ptr.add(i), &mut *self.ptr.add(j))
+    }
+}
@@ -0,0 +1,262 @@
+use nalgebra_glm::{vec2, Vec2};
+
+use crate::engine2::{
+    aabb::Aabb,
+    broad_phase::uniform_grid::UniformGrid,
+    handle::{HandleMap, ObjectHandle},
+    verlet_object::VerletObject,
+};
+
+/// Closest intersection of a ray with an object.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub handle: ObjectHandle,
+    /// Distance from the ray origin, zero when the origin is inside the object.
+    pub distance: f32,
+    pub point: Vec2,
+    /// Unit normal of the object's surface at ``point``.
+    pub normal: Vec2,
+}
+
+/// Read-only spatial queries over the objects of a ``World``, see ``World::query``.
+pub struct SpatialQuery<'a> {
+    objects: &'a [VerletObject],
+    handles: &'a HandleMap,
+    grid: &'a UniformGrid,
+}
+impl<'a> SpatialQuery<'a> {
+    pub(crate) const fn new(
+        objects: &'a [VerletObject],
+        handles: &'a HandleMap,
+        grid: &'a UniformGrid,
+    ) -> Self {
+        Self {
+            objects,
+            handles,
+            grid,
+        }
+    }
+
+    /// Objects containing ``point``.
+    #[must_use]
+    pub fn point(&self, point: Vec2) -> Vec<ObjectHandle> {
+        self.collect(&Aabb::new(point, point), |obj| {
+            obj.get_center().metric_distance(&point) <= obj.get_radius()
+        })
+    }
+
+    /// Objects overlapping ``area``.
+    #[must_use]
+    pub fn aabb(&self, area: &Aabb) -> Vec<ObjectHandle> {
+        self.collect(area, |obj| {
+            let center = obj.get_center();
+            let closest = vec2(
+                center.x.clamp(area.min.x, area.max.x),
+                center.y.clamp(area.min.y, area.max.y),
+            );
+            center.metric_distance(&closest) < obj.get_radius()
+        })
+    }
+
+    /// Objects overlapping the disc at ``center``.
+    #[must_use]
+    pub fn circle(&self, center: Vec2, radius: f32) -> Vec<ObjectHandle> {
+        self.collect(&Aabb::around(center, radius), |obj| {
+            obj.get_center().metric_distance(&center) < obj.get_radius() + radius
+        })
+    }
+
+    /// Object whose surface is closest to ``point`` and the distance to it (negative if
+    /// ``point`` is inside).
+    #[must_use]
+    pub fn nearest(&self, point: Vec2) -> Option<(ObjectHandle, f32)> {
+        if self.objects.is_empty() {
+            return None;
+        }
+        let surface_distance = |obj_idx: usize| {
+            let obj = &self.objects[obj_idx];
+            obj.get_center().metric_distance(&point) - obj.get_radius()
+        };
+        let closest_in = |area: &Aabb| {
+            self.candidates(area)
+                .map(|obj_idx| (obj_idx, surface_distance(obj_idx)))
+                .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+        };
+
+        // grow the search area until it finds something
+        let whole_grid = self.grid.bounds().merge(&Aabb::new(point, point));
+        let mut half_size = self.grid.cell_size();
+        let first_guess = loop {
+            let area = Aabb::around(point, half_size);
+            if let Some(found) = closest_in(&area) {
+                break found;
+            }
+            if area.contains(&whole_grid) {
+                return None;
+            }
+            half_size *= 2.0;
+        };
+
+        // anything closer than the first guess has its center within this area
+        let area = Aabb::around(point, first_guess.1.max(0.0) + self.grid.max_radius());
+        let (obj_idx, distance) = closest_in(&area).unwrap_or(first_guess);
+        Some((self.handle(obj_idx), distance))
+    }
+
+    /// First object hit by the ray, ``direction`` doesn't have to be normalized.
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn raycast(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> Option<RayHit> {
+        if self.objects.is_empty() || direction.magnitude_squared() <= f32::EPSILON {
+            return None;
+        }
+        let direction = direction.normalize();
+
+        // every hit point lies inside the grid
+        let bounds = self.grid.bounds();
+        let (t_enter, t_exit) = ray_box(origin, direction, &bounds)?;
+        let t_start = t_enter.max(0.0);
+        let t_end = t_exit.min(max_distance);
+        if t_start > t_end {
+            return None;
+        }
+
+        // walk the cells along the ray (DDA), an object hit inside a cell has its center
+        // in that cell or in one of its neighbours
+        let cell_size = self.grid.cell_size();
+        let (mut col, mut row) = self.grid.cell_coords(origin + direction * t_start);
+        let axis = |dir: f32, origin: f32, grid_origin: f32, cell: usize| {
+            if dir.abs() <= f32::EPSILON {
+                return (0, f32::INFINITY, f32::INFINITY);
+            }
+            let step = if dir > 0.0 { 1 } else { -1 };
+            let boundary_cell = if dir > 0.0 { cell + 1 } else { cell };
+            let boundary = (boundary_cell as f32).mul_add(cell_size, grid_origin);
+            (step, (boundary - origin) / dir, cell_size / dir.abs())
+        };
+        let (step_col, mut t_max_col, t_delta_col) = axis(direction.x, origin.x, bounds.min.x, col);
+        let (step_row, mut t_max_row, t_delta_row) = axis(direction.y, origin.y, bounds.min.y, row);
+
+        let mut best: Option<RayHit> = None;
+        let mut t_cell = t_start;
+        loop {
+            if t_cell > t_end || best.is_some_and(|hit| hit.distance < t_cell) {
+                break;
+            }
+            for obj_idx in self.neighbourhood(col, row) {
+                let obj = &self.objects[obj_idx];
+                let Some((distance, normal)) =
+                    ray_circle(origin, direction, obj.get_center(), obj.get_radius())
+                else {
+                    continue;
+                };
+                if distance <= max_distance && best.is_none_or(|hit| distance < hit.distance) {
+                    best = Some(RayHit {
+                        handle: self.handle(obj_idx),
+                        distance,
+                        point: origin + direction * distance,
+                        normal,
+                    });
+                }
+            }
+
+            let next = if t_max_col < t_max_row {
+                t_cell = t_max_col;
+                t_max_col += t_delta_col;
+                col.checked_add_signed(step_col)
+                    .filter(|&col| col < self.grid.cols())
+                    .map(|c| (c, row))
+            } else {
+                t_cell = t_max_row;
+                t_max_row += t_delta_row;
+                row.checked_add_signed(step_row)
+                    .filter(|&row| row < self.grid.rows())
+                    .map(|r| (col, r))
+            };
+            let Some(next) = next else {
+                break;
+            };
+            (col, row) = next;
+        }
+        best
+    }
+
+    fn handle(&self, obj_idx: usize) -> ObjectHandle {
+        self.handles
+            .handle_at(obj_idx)
+            .expect("Spatial query out of sync with the world.")
+    }
+
+    fn collect(&self, area: &Aabb, predicate: impl Fn(&VerletObject) -> bool) -> Vec<ObjectHandle> {
+        self.candidates(area)
+            .filter(|&obj_idx| predicate(&self.objects[obj_idx]))
+            .map(|obj_idx| self.handle(obj_idx))
+            .collect()
+    }
+
+    /// Indices of objects stored in cells that can hold objects overlapping ``area``.
+    fn candidates(&self, area: &Aabb) -> impl Iterator<Item = usize> + '_ {
+        let ((min_col, max_col), (min_row, max_row)) = if self.objects.is_empty() {
+            ((1, 0), (1, 0))
+        } else {
+            self.grid.cells_overlapping(area)
+        };
+        (min_row..=max_row).flat_map(move |row| {
+            (min_col..=max_col).flat_map(move |col| self.grid.cell(col, row).iter().copied())
+        })
+    }
+
+    fn neighbourhood(&self, col: usize, row: usize) -> impl Iterator<Item = usize> + '_ {
+        let cols = col.saturating_sub(1)..=(col + 1).min(self.grid.cols() - 1);
+        let rows = row.saturating_sub(1)..=(row + 1).min(self.grid.rows() - 1);
+        rows.flat_map(move |row| {
+            cols.clone()
+                .flat_map(move |col| self.grid.cell(col, row).iter().copied())
+        })
+    }
+}
+
+/// Parameter range in which the ray is inside ``aabb``.
+fn ray_box(origin: Vec2, direction: Vec2, aabb: &Aabb) -> Option<(f32, f32)> {
+    let mut t_enter = f32::NEG_INFINITY;
+    let mut t_exit = f32::INFINITY;
+    for axis in 0..2 {
+        if direction[axis].abs() <= f32::EPSILON {
+            if origin[axis] < aabb.min[axis] || origin[axis] > aabb.max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (aabb.min[axis] - origin[axis]) / direction[axis];
+        let t2 = (aabb.max[axis] - origin[axis]) / direction[axis];
+        t_enter = t_enter.max(t1.min(t2));
+        t_exit = t_exit.min(t1.max(t2));
+    }
+    (t_enter <= t_exit && t_exit >= 0.0).then_some((t_enter, t_exit))
+}
+
+/// Distance along a normalized ray to the circle and the surface normal there.
+fn ray_circle(origin: Vec2, direction: Vec2, center: Vec2, radius: f32) -> Option<(f32, Vec2)> {
+    let to_origin = origin - center;
+    let c = radius.mul_add(-radius, to_origin.magnitude_squared());
+    if c <= 0.0 {
+        let normal = if to_origin.magnitude_squared() > f32::EPSILON {
+            to_origin.normalize()
+        } else {
+            -direction
+        };
+        return Some((0.0, normal));
+    }
+    let b = to_origin.dot(&direction);
+    if b > 0.0 {
+        // pointing away
+        return None;
+    }
+    let discriminant = b.mul_add(b, -c);
+    if discriminant < 0.0 {
+        return None;
+    }
+    let distance = -b - discriminant.sqrt();
+    let normal = (origin + direction * distance - center) / radius;
+    Some((distance, normal))
+}
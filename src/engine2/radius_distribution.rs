@@ -0,0 +1,313 @@
+use std::{f32::consts::TAU, fmt};
+
+use rand::Rng;
+
+/// Smallest radius ``RadiusDistribution::sample`` returns, tails reaching below zero are
+/// clamped to it.
+pub const MIN_RADIUS: f32 = 1e-4;
+
+// standard deviations above the mean covered by ``upper_bound`` of unbounded distributions
+const TAIL_SIGMAS: f32 = 3.0;
+
+/// Distribution the radii of generated objects are drawn from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RadiusDistribution {
+    /// Uniform in ``radius ± deviation``.
+    Uniform {
+        radius: f32,
+        deviation: f32,
+    },
+    Normal {
+        mean: f32,
+        std_dev: f32,
+    },
+    /// Radius whose natural logarithm is normally distributed with ``mu`` and ``sigma``.
+    LogNormal {
+        mu: f32,
+        sigma: f32,
+    },
+    /// ``(weight, radius)`` pairs, radii are picked with probabilities proportional to
+    /// their weights.
+    Discrete(Vec<(f32, f32)>),
+    /// ``(weight, distribution)`` pairs, distributions are picked with probabilities
+    /// proportional to their weights.
+    Mixture(Vec<(f32, Self)>),
+}
+impl RadiusDistribution {
+    /// Mixture of two normal distributions, ``first_proportion`` of the radii come from the
+    /// first one.
+    #[must_use]
+    pub fn bimodal(first: (f32, f32), second: (f32, f32), first_proportion: f32) -> Self {
+        Self::Mixture(vec![
+            (
+                first_proportion,
+                Self::Normal {
+                    mean: first.0,
+                    std_dev: first.1,
+                },
+            ),
+            (
+                1.0 - first_proportion,
+                Self::Normal {
+                    mean: second.0,
+                    std_dev: second.1,
+                },
+            ),
+        ])
+    }
+
+    /// Draws a radius, never smaller than ``MIN_RADIUS``.
+    pub fn sample(&self, rng: &mut impl Rng) -> f32 {
+        self.sample_unclamped(rng).max(MIN_RADIUS)
+    }
+
+    fn sample_unclamped(&self, rng: &mut impl Rng) -> f32 {
+        match self {
+            Self::Uniform { radius, deviation } => {
+                let randomizer = rng.gen::<f32>().mul_add(2.0, -1.0);
+                randomizer.mul_add(*deviation, *radius)
+            }
+            Self::Normal { mean, std_dev } => standard_normal(rng).mul_add(*std_dev, *mean),
+            Self::LogNormal { mu, sigma } => standard_normal(rng).mul_add(*sigma, *mu).exp(),
+            Self::Discrete(radii) => pick(radii, rng).copied().unwrap_or(MIN_RADIUS),
+            Self::Mixture(members) => {
+                pick(members, rng).map_or(MIN_RADIUS, |member| member.sample_unclamped(rng))
+            }
+        }
+    }
+
+    /// Expected radius, ignoring clamping.
+    #[must_use]
+    pub fn mean(&self) -> f32 {
+        self.moments().0
+    }
+
+    /// Standard deviation of the radius, ignoring clamping.
+    #[must_use]
+    pub fn std_dev(&self) -> f32 {
+        let (mean, second_moment) = self.moments();
+        mean.mul_add(-mean, second_moment).max(0.0).sqrt()
+    }
+
+    /// Largest radius to plan for when spacing objects. Exact for bounded distributions,
+    /// three standard deviations above the mean for the normal tails.
+    pub fn upper_bound(&self) -> f32 {
+        let bound = match self {
+            Self::Uniform { radius, deviation } => radius + deviation.abs(),
+            Self::Normal { mean, std_dev } => std_dev.abs().mul_add(TAIL_SIGMAS, *mean),
+            Self::LogNormal { mu, sigma } => sigma.abs().mul_add(TAIL_SIGMAS, *mu).exp(),
+            Self::Discrete(radii) => weighted(radii)
+                .map(|(_, &radius)| radius)
+                .fold(MIN_RADIUS, f32::max),
+            Self::Mixture(members) => weighted(members)
+                .map(|(_, member)| member.upper_bound())
+                .fold(MIN_RADIUS, f32::max),
+        };
+        bound.max(MIN_RADIUS)
+    }
+
+    /// Mean and mean of squares.
+    fn moments(&self) -> (f32, f32) {
+        match self {
+            Self::Uniform { radius, deviation } => {
+                (*radius, radius.mul_add(*radius, deviation.powi(2) / 3.0))
+            }
+            Self::Normal { mean, std_dev } => (*mean, mean.mul_add(*mean, std_dev.powi(2))),
+            Self::LogNormal { mu, sigma } => {
+                let variance = sigma.powi(2);
+                (
+                    (mu + variance / 2.0).exp(),
+                    2.0f32.mul_add(*mu, 2.0 * variance).exp(),
+                )
+            }
+            Self::Discrete(radii) => {
+                mixture_moments(weighted(radii).map(|(w, &radius)| (w, radius, radius.powi(2))))
+            }
+            Self::Mixture(members) => mixture_moments(weighted(members).map(|(w, member)| {
+                let (mean, second_moment) = member.moments();
+                (w, mean, second_moment)
+            })),
+        }
+    }
+}
+impl Default for RadiusDistribution {
+    fn default() -> Self {
+        Self::Uniform {
+            radius: 0.003,
+            deviation: 0.0015,
+        }
+    }
+}
+
+/// Summary of a set of radii, e.g. of the objects a generator produced.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RadiusStats {
+    pub count: usize,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub std_dev: f32,
+}
+impl RadiusStats {
+    /// ``None`` for an empty set.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn from_radii(radii: impl IntoIterator<Item = f32>) -> Option<Self> {
+        let mut count = 0;
+        let (mut min, mut max) = (f32::INFINITY, f32::NEG_INFINITY);
+        let (mut sum, mut sum_of_squares) = (0.0f64, 0.0f64);
+        for radius in radii {
+            count += 1;
+            min = min.min(radius);
+            max = max.max(radius);
+            sum += f64::from(radius);
+            sum_of_squares += f64::from(radius).powi(2);
+        }
+        if count == 0 {
+            return None;
+        }
+        let mean = sum / count as f64;
+        let variance = mean.mul_add(-mean, sum_of_squares / count as f64).max(0.0);
+        #[allow(clippy::cast_possible_truncation)]
+        Some(Self {
+            count,
+            min,
+            max,
+            mean: mean as f32,
+            std_dev: variance.sqrt() as f32,
+        })
+    }
+}
+impl fmt::Display for RadiusStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} radii, min {:.5}, max {:.5}, mean {:.5}, std dev {:.5}",
+            self.count, self.min, self.max, self.mean, self.std_dev
+        )
+    }
+}
+
+/// Box-Muller transform.
+fn standard_normal(rng: &mut impl Rng) -> f32 {
+    // 1 - [0, 1) keeps the logarithm finite
+    let u1 = 1.0 - rng.gen::<f32>();
+    let u2 = rng.gen::<f32>();
+    (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+}
+
+/// Entries with a positive weight.
+fn weighted<T>(entries: &[(f32, T)]) -> impl Iterator<Item = (f32, &T)> {
+    entries
+        .iter()
+        .filter(|(weight, _)| *weight > 0.0)
+        .map(|(weight, value)| (*weight, value))
+}
+
+fn pick<'a, T>(entries: &'a [(f32, T)], rng: &mut impl Rng) -> Option<&'a T> {
+    let total: f32 = weighted(entries).map(|(weight, _)| weight).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut remaining = rng.gen::<f32>() * total;
+    let mut last = None;
+    for (weight, value) in weighted(entries) {
+        if remaining < weight {
+            return Some(value);
+        }
+        remaining -= weight;
+        last = Some(value);
+    }
+    // rounding left a sliver past the last entry
+    last
+}
+
+/// Combines ``(weight, mean, mean of squares)`` of the members.
+fn mixture_moments(members: impl Iterator<Item = (f32, f32, f32)>) -> (f32, f32) {
+    let (total, mean, second_moment) = members.fold((0.0, 0.0, 0.0), |acc, (w, mean, sq)| {
+        (acc.0 + w, w.mul_add(mean, acc.1), w.mul_add(sq, acc.2))
+    });
+    if total <= 0.0 {
+        return (0.0, 0.0);
+    }
+    (mean / total, second_moment / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{RadiusDistribution, RadiusStats, MIN_RADIUS};
+
+    const SAMPLES: usize = 20_000;
+
+    fn sampled(distribution: &RadiusDistribution) -> RadiusStats {
+        let mut rng = StdRng::seed_from_u64(3);
+        RadiusStats::from_radii((0..SAMPLES).map(|_| distribution.sample(&mut rng))).unwrap()
+    }
+
+    #[test]
+    fn negative_tails_are_clamped() {
+        let distribution = RadiusDistribution::Normal {
+            mean: 0.0,
+            std_dev: 0.01,
+        };
+        let stats = sampled(&distribution);
+        assert!((stats.min - MIN_RADIUS).abs() < f32::EPSILON);
+        assert!(stats.max > MIN_RADIUS);
+        assert!((distribution.upper_bound() - 0.03).abs() < 1e-6);
+
+        // no positive weights, nothing to pick from
+        let empty = RadiusDistribution::Discrete(vec![(0.0, 0.5)]);
+        assert!((sampled(&empty).max - MIN_RADIUS).abs() < f32::EPSILON);
+        assert!((empty.upper_bound() - MIN_RADIUS).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn samples_stay_below_the_upper_bound() {
+        let distribution = RadiusDistribution::Uniform {
+            radius: 0.003,
+            deviation: 0.0015,
+        };
+        let stats = sampled(&distribution);
+        assert!(stats.min >= 0.0015 && stats.max <= distribution.upper_bound());
+        assert!((distribution.upper_bound() - 0.0045).abs() < 1e-7);
+
+        let log_normal = RadiusDistribution::LogNormal {
+            mu: -5.0,
+            sigma: 0.2,
+        };
+        assert!((log_normal.upper_bound() - (-4.4f32).exp()).abs() < 1e-7);
+    }
+
+    #[test]
+    fn mixture_follows_its_weights() {
+        let distribution = RadiusDistribution::Mixture(vec![
+            (3.0, RadiusDistribution::Discrete(vec![(1.0, 0.01)])),
+            (1.0, RadiusDistribution::Discrete(vec![(1.0, 0.03)])),
+            (0.0, RadiusDistribution::Discrete(vec![(1.0, 0.5)])),
+        ]);
+        assert!((distribution.upper_bound() - 0.03).abs() < f32::EPSILON);
+        assert!((distribution.mean() - 0.015).abs() < 1e-6);
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let small = (0..SAMPLES)
+            .filter(|_| distribution.sample(&mut rng) < 0.02)
+            .count();
+        #[allow(clippy::cast_precision_loss)]
+        let fraction = small as f32 / SAMPLES as f32;
+        assert!((fraction - 0.75).abs() < 0.02);
+    }
+
+    #[test]
+    fn stats_match_the_moments() {
+        let distribution = RadiusDistribution::bimodal((0.01, 0.001), (0.02, 0.002), 0.4);
+        let stats = sampled(&distribution);
+        assert_eq!(stats.count, SAMPLES);
+        // normal tails may pass ``upper_bound``, only clamping bounds them
+        assert!(stats.min >= MIN_RADIUS);
+        assert!((stats.mean - distribution.mean()).abs() < 0.02 * distribution.mean());
+        assert!((stats.std_dev - distribution.std_dev()).abs() < 0.05 * distribution.std_dev());
+
+        assert!(RadiusStats::from_radii([]).is_none());
+    }
+}
@@ -0,0 +1,457 @@
+use std::{
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use nalgebra_glm::{vec2, Vec2};
+
+use crate::engine2::{container::Container, verlet_object::VerletObject, world::World};
+
+/// File formats an ``Image`` can be saved in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    /// Binary PPM (``P6``), alpha is dropped.
+    Ppm,
+}
+impl ImageFormat {
+    /// Picks the format from the extension of ``path``.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(Self::Png),
+            "ppm" => Some(Self::Ppm),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    Encoding(png::EncodingError),
+    Decoding(png::DecodingError),
+    /// Only 8-bit RGB and RGBA images can be loaded.
+    UnsupportedColor(png::ColorType, png::BitDepth),
+}
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "image i/o failed: {error}"),
+            Self::Encoding(error) => write!(f, "png encoding failed: {error}"),
+            Self::Decoding(error) => write!(f, "png decoding failed: {error}"),
+            Self::UnsupportedColor(color, depth) => {
+                write!(f, "{color:?} png with {depth:?} bit depth is not supported")
+            }
+        }
+    }
+}
+impl Error for ImageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Encoding(error) => Some(error),
+            Self::Decoding(error) => Some(error),
+            Self::UnsupportedColor(..) => None,
+        }
+    }
+}
+impl From<io::Error> for ImageError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+impl From<png::EncodingError> for ImageError {
+    fn from(error: png::EncodingError) -> Self {
+        Self::Encoding(error)
+    }
+}
+impl From<png::DecodingError> for ImageError {
+    fn from(error: png::DecodingError) -> Self {
+        Self::Decoding(error)
+    }
+}
+
+/// RGBA image with 8 bits per channel, rows go from top to bottom.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+impl Image {
+    /// Image filled with ``color``.
+    #[must_use]
+    pub fn new(width: u32, height: u32, color: [f32; 4]) -> Self {
+        let pixels = width as usize * height as usize;
+        Self {
+            width,
+            height,
+            data: to_bytes(color).repeat(pixels),
+        }
+    }
+
+    #[must_use]
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[must_use]
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Raw RGBA bytes, row by row.
+    #[must_use]
+    pub fn as_rgba(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// # Panics
+    /// If the pixel is outside of the image.
+    #[must_use]
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = self.offset(x, y);
+        [0, 1, 2, 3].map(|channel| self.data[offset + channel])
+    }
+
+    /// Largest difference of any channel of any pixel, ``None`` if the sizes differ.
+    #[must_use]
+    pub fn max_difference(&self, other: &Self) -> Option<u8> {
+        if (self.width, self.height) != (other.width, other.height) {
+            return None;
+        }
+        Some(
+            self.data
+                .iter()
+                .zip(&other.data)
+                .map(|(a, b)| a.abs_diff(*b))
+                .max()
+                .unwrap_or(0),
+        )
+    }
+
+    /// Draws ``color`` over the pixel with its alpha scaled by ``coverage``.
+    fn blend(&mut self, x: u32, y: u32, color: [f32; 4], coverage: f32) {
+        let alpha = color[3] * coverage;
+        if alpha <= 0.0 {
+            return;
+        }
+        let offset = self.offset(x, y);
+        let pixel = &mut self.data[offset..offset + 4];
+        let destination = [0, 1, 2, 3].map(|channel| f32::from(pixel[channel]) / 255.0);
+        let blended = [
+            color[0].mul_add(alpha, destination[0] * (1.0 - alpha)),
+            color[1].mul_add(alpha, destination[1] * (1.0 - alpha)),
+            color[2].mul_add(alpha, destination[2] * (1.0 - alpha)),
+            alpha.mul_add(1.0 - destination[3], destination[3]),
+        ];
+        pixel.copy_from_slice(&to_bytes(blended));
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "Pixel out of the image.");
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+
+    /// # Errors
+    /// If writing or encoding fails.
+    pub fn write(&self, mut writer: impl Write, format: ImageFormat) -> Result<(), ImageError> {
+        match format {
+            ImageFormat::Png => {
+                let mut encoder = png::Encoder::new(&mut writer, self.width, self.height);
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                let mut png_writer = encoder.write_header()?;
+                png_writer.write_image_data(&self.data)?;
+                png_writer.finish()?;
+                writer.flush()?;
+            }
+            ImageFormat::Ppm => {
+                write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+                for pixel in self.data.chunks_exact(4) {
+                    writer.write_all(&pixel[..3])?;
+                }
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads an 8-bit RGB or RGBA png, e.g. a golden image to compare against.
+    ///
+    /// # Errors
+    /// If reading or decoding fails or the png has another color type.
+    pub fn read_png(reader: impl Read) -> Result<Self, ImageError> {
+        let mut reader = png::Decoder::new(reader).read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        buffer.truncate(info.buffer_size());
+
+        let data = match (info.color_type, info.bit_depth) {
+            (png::ColorType::Rgba, png::BitDepth::Eight) => buffer,
+            (png::ColorType::Rgb, png::BitDepth::Eight) => buffer
+                .chunks_exact(3)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], u8::MAX])
+                .collect(),
+            (color, depth) => return Err(ImageError::UnsupportedColor(color, depth)),
+        };
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            data,
+        })
+    }
+
+    /// # Errors
+    /// If writing the file fails.
+    pub fn save(&self, path: impl AsRef<Path>, format: ImageFormat) -> Result<(), ImageError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer, format)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// # Errors
+    /// If the file can't be read or isn't a supported png.
+    pub fn load_png(path: impl AsRef<Path>) -> Result<Self, ImageError> {
+        Self::read_png(BufReader::new(File::open(path)?))
+    }
+}
+
+/// Draws a ``World`` into an ``Image`` on the CPU, no window or OpenGL needed.
+///
+/// The world is mapped to the image like to the window: ``(-1, -1)`` is the bottom left
+/// corner and ``(1, 1)`` the top right one. Objects are anti-aliased discs drawn in the
+/// same order and with the same outline as the OpenGL renderer, on top of the container
+/// outline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoftwareRenderer {
+    pub width: u32,
+    pub height: u32,
+    pub background_color: [f32; 4],
+    /// ``None`` leaves the container out.
+    pub container_color: Option<[f32; 4]>,
+    /// In pixels.
+    pub container_line_width: f32,
+    /// In pixels, zero turns the outline of the objects off.
+    pub outline_width: f32,
+    pub outline_color: [f32; 4],
+}
+impl SoftwareRenderer {
+    /// Black background, one pixel wide grey container outline and no object outlines.
+    #[must_use]
+    pub const fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            background_color: [0.0, 0.0, 0.0, 1.0],
+            container_color: Some([0.5, 0.5, 0.5, 1.0]),
+            container_line_width: 1.0,
+            outline_width: 0.0,
+            outline_color: [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+
+    #[must_use]
+    pub const fn with_background_color(mut self, color: [f32; 4]) -> Self {
+        self.background_color = color;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_container(mut self, color: Option<[f32; 4]>, line_width: f32) -> Self {
+        self.container_color = color;
+        self.container_line_width = line_width;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_outline(mut self, width: f32, color: [f32; 4]) -> Self {
+        self.outline_width = width;
+        self.outline_color = color;
+        self
+    }
+
+    pub fn render(&self, world: &World) -> Image {
+        let mut image = Image::new(self.width, self.height, self.background_color);
+        if let Some(color) = self.container_color {
+            self.draw_container(&mut image, world.container(), color);
+        }
+        for obj in world.objects() {
+            self.draw_object(&mut image, obj);
+        }
+        image
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn pixels_per_unit(&self) -> Vec2 {
+        vec2(self.width as f32, self.height as f32) / 2.0
+    }
+
+    /// Image coordinates of a world point, in pixels.
+    #[allow(clippy::cast_precision_loss)]
+    fn to_image(&self, point: Vec2) -> Vec2 {
+        vec2(point.x + 1.0, 1.0 - point.y).component_mul(&self.pixels_per_unit())
+    }
+
+    /// World coordinates of the center of a pixel.
+    #[allow(clippy::cast_precision_loss)]
+    fn to_world(&self, x: u32, y: u32) -> Vec2 {
+        let pixel_center = vec2(x as f32 + 0.5, y as f32 + 0.5);
+        let unit = pixel_center.component_div(&self.pixels_per_unit());
+        vec2(unit.x - 1.0, 1.0 - unit.y)
+    }
+
+    fn draw_container(&self, image: &mut Image, container: &Container, color: [f32; 4]) {
+        let units_per_pixel = 1.0 / self.pixels_per_unit().min();
+        let half_width = self.container_line_width / 2.0;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (distance, _) = container.signed_distance(self.to_world(x, y));
+                let coverage =
+                    (half_width + 0.5 - distance.abs() / units_per_pixel).clamp(0.0, 1.0);
+                image.blend(x, y, color, coverage);
+            }
+        }
+    }
+
+    /// Same coverage and outline as the circle fragment shader.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn draw_object(&self, image: &mut Image, obj: &VerletObject) {
+        let center = self.to_image(obj.get_center());
+        let radii = self.pixels_per_unit() * obj.get_radius();
+        // disc units per pixel
+        let pixel = 1.0 / radii.min().max(f32::EPSILON);
+
+        let min = (center - radii).map(|v| v.floor().max(0.0));
+        let max = center + radii;
+        if min.x >= self.width as f32 || min.y >= self.height as f32 || max.x < 0.0 || max.y < 0.0 {
+            return;
+        }
+        let (max_x, max_y) = (
+            (max.x as u32).min(self.width - 1),
+            (max.y as u32).min(self.height - 1),
+        );
+        for y in min.y as u32..=max_y {
+            for x in min.x as u32..=max_x {
+                let local = (vec2(x as f32 + 0.5, y as f32 + 0.5) - center).component_div(&radii);
+                let inside = 1.0 - local.magnitude();
+                let coverage = (inside / pixel + 0.5).clamp(0.0, 1.0);
+                if coverage <= 0.0 {
+                    continue;
+                }
+                let color = if self.outline_width > 0.0 {
+                    let outline = (self.outline_width + 0.5 - inside / pixel).clamp(0.0, 1.0);
+                    mix(obj.get_color(), self.outline_color, outline)
+                } else {
+                    obj.get_color()
+                };
+                image.blend(x, y, color, coverage);
+            }
+        }
+    }
+}
+
+fn mix(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    [0, 1, 2, 3].map(|channel| (b[channel] - a[channel]).mul_add(t, a[channel]))
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn to_bytes(color: [f32; 4]) -> [u8; 4] {
+    color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+
+    use nalgebra_glm::vec2;
+
+    use super::{Image, ImageFormat, SoftwareRenderer};
+    use crate::engine2::{container::Container, verlet_object::VerletObject, world::World};
+
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+    fn single_object_world() -> World {
+        let mut world = World::new();
+        world.set_container(Container::rectangle(vec2(0.0, 0.0), 1.531_25, 1.531_25));
+        world.add_object(VerletObject::new(vec2(0.5, 0.5), 0.25, RED));
+        world
+    }
+
+    #[test]
+    fn renders_objects_and_container() {
+        let image = SoftwareRenderer::new(64, 64).render(&single_object_world());
+
+        // (0.5, 0.5) lands on the corner of pixel (48, 16), the disc radius is 8 pixels
+        assert_eq!(image.pixel(48, 16), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(32, 32), [0, 0, 0, 255]);
+        let edge = image.pixel(53, 21);
+        assert!(
+            edge[0] > 0 && edge[0] < 255,
+            "edge isn't anti-aliased: {edge:?}"
+        );
+        // the left side of the container runs through the centers of column 7
+        assert_eq!(image.pixel(7, 32), [128, 128, 128, 255]);
+        assert_eq!(image.pixel(3, 32), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn outline_colors_the_edge() {
+        let image = SoftwareRenderer::new(64, 64)
+            .with_container(None, 0.0)
+            .with_outline(2.0, [0.0, 0.0, 1.0, 1.0])
+            .render(&single_object_world());
+
+        assert_eq!(image.pixel(48, 16), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(54, 16), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let image = SoftwareRenderer::new(32, 24).render(&single_object_world());
+        let mut bytes = Vec::new();
+        image.write(&mut bytes, ImageFormat::Png).unwrap();
+        let loaded = Image::read_png(bytes.as_slice()).unwrap();
+        assert_eq!(image.max_difference(&loaded), Some(0));
+    }
+
+    #[test]
+    fn ppm_drops_alpha() {
+        let image = Image::new(3, 2, [0.0, 1.0, 0.0, 0.5]);
+        let mut bytes = Vec::new();
+        image.write(&mut bytes, ImageFormat::Ppm).unwrap();
+        let header = b"P6\n3 2\n255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(bytes.len(), header.len() + 3 * 2 * 3);
+        assert_eq!(&bytes[header.len()..header.len() + 3], [0, 255, 0]);
+    }
+
+    // accepts everything until it's flushed, like a buffered file on a full disk
+    struct FullDisk;
+    impl Write for FullDisk {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"))
+        }
+    }
+
+    #[test]
+    fn write_reports_flush_errors() {
+        let image = Image::new(4, 4, RED);
+        for format in [ImageFormat::Png, ImageFormat::Ppm] {
+            assert!(image.write(FullDisk, format).is_err());
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::Path,
+};
+
+use nalgebra_glm::Vec2;
+
+use crate::engine2::{
+    clock::SimulationClock,
+    container::{ContactMaterial, Container},
+    forces::SavedForce,
+    snapshot::{
+        decode_container, decode_force, decode_vec2, encode_container, encode_force, encode_vec2,
+        malformed, Decoder, Encoder, SnapshotError, TextDecoder, TextEncoder, WorldSnapshot,
+        OPTION, VERSION as SNAPSHOT_VERSION,
+    },
+    world::World,
+};
+
+/// Version written into new recordings, bumped whenever the layout changes.
+pub const VERSION: u32 = 1;
+
+const TEXT_HEADER: &str = "engine2-replay";
+const ACTIONS: &[&str] = &[
+    "spawn",
+    "erase",
+    "container",
+    "material",
+    "kill_region",
+    "add_force",
+    "clear_forces",
+];
+
+/// User input that changes a running ``World``.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Spawn {
+        center: Vec2,
+        radius: f32,
+    },
+    /// Removes every object overlapping the disc.
+    Erase {
+        center: Vec2,
+        radius: f32,
+    },
+    SetContainer(Container),
+    SetContainerMaterial(ContactMaterial),
+    SetKillRegion(Option<Container>),
+    AddForce(SavedForce),
+    ClearForces,
+}
+impl Action {
+    pub fn apply(&self, world: &mut World) {
+        match self {
+            Self::Spawn { center, radius } => {
+                world.add_obj_at(*center, *radius);
+            }
+            Self::Erase { center, radius } => {
+                for handle in world.query().circle(*center, *radius) {
+                    // handles fresh from the query can't be stale
+                    let _ = world.remove_object(handle);
+                }
+            }
+            Self::SetContainer(container) => world.set_container(container.clone()),
+            Self::SetContainerMaterial(material) => world.set_container_material(*material),
+            Self::SetKillRegion(region) => world.set_kill_region(region.clone()),
+            Self::AddForce(force) => {
+                world.add_force(force.clone().into_generator());
+            }
+            Self::ClearForces => world.clear_forces(),
+        }
+    }
+
+    fn encode(&self, e: &mut dyn Encoder) {
+        match self {
+            Self::Spawn { center, radius } | Self::Erase { center, radius } => {
+                e.variant(ACTIONS, usize::from(matches!(self, Self::Erase { .. })));
+                encode_vec2(e, *center);
+                e.float(*radius);
+            }
+            Self::SetContainer(container) => {
+                e.variant(ACTIONS, 2);
+                encode_container(e, container);
+            }
+            Self::SetContainerMaterial(material) => {
+                e.variant(ACTIONS, 3);
+                e.float(material.restitution);
+                e.float(material.friction);
+            }
+            Self::SetKillRegion(region) => {
+                e.variant(ACTIONS, 4);
+                match region {
+                    None => e.variant(OPTION, 0),
+                    Some(region) => {
+                        e.variant(OPTION, 1);
+                        encode_container(e, region);
+                    }
+                }
+            }
+            Self::AddForce(force) => {
+                e.variant(ACTIONS, 5);
+                encode_force(e, force);
+            }
+            Self::ClearForces => e.variant(ACTIONS, 6),
+        }
+    }
+
+    fn decode(d: &mut dyn Decoder) -> Result<Self, SnapshotError> {
+        Ok(match d.variant(ACTIONS)? {
+            0 => Self::Spawn {
+                center: decode_vec2(d)?,
+                radius: d.float()?,
+            },
+            1 => Self::Erase {
+                center: decode_vec2(d)?,
+                radius: d.float()?,
+            },
+            2 => Self::SetContainer(decode_container(d)?),
+            3 => Self::SetContainerMaterial(ContactMaterial::new(d.float()?, d.float()?)),
+            4 => Self::SetKillRegion(match d.variant(OPTION)? {
+                0 => None,
+                _ => Some(decode_container(d)?),
+            }),
+            5 => Self::AddForce(decode_force(d)?),
+            _ => Self::ClearForces,
+        })
+    }
+}
+
+/// Initial state of a world and everything done to it afterwards, enough to rerun the
+/// session tick by tick. Only reproducible with sequential collision solving.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording {
+    pub tick: f32,
+    pub substeps: usize,
+    /// Number of ticks simulated while recording.
+    pub ticks: u64,
+    pub initial_state: WorldSnapshot,
+    /// Actions in the order they happened, each with the index of the tick it preceded.
+    pub actions: Vec<(u64, Action)>,
+}
+impl Recording {
+    /// Reruns the session in a new headless world and returns it in its final state.
+    #[must_use]
+    pub fn replay(&self) -> World {
+        let mut world = World::new();
+        world.restore(self.initial_state.clone());
+
+        let mut actions = self.actions.iter().peekable();
+        for tick in 0..=self.ticks {
+            while let Some((_, action)) = actions.next_if(|(action_tick, _)| *action_tick <= tick) {
+                action.apply(&mut world);
+            }
+            if tick < self.ticks {
+                world.update(self.tick, self.substeps);
+            }
+        }
+        world
+    }
+
+    /// Writes the recording in a text format.
+    ///
+    /// # Errors
+    /// If writing fails.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn write(&self, mut writer: impl Write) -> Result<(), SnapshotError> {
+        let mut e = TextEncoder::new(TEXT_HEADER, VERSION);
+        e.label("tick");
+        e.float(self.tick);
+        e.label("substeps");
+        e.count(self.substeps);
+        e.label("ticks");
+        e.count(self.ticks as usize);
+
+        e.label("initial_state");
+        e.count(SNAPSHOT_VERSION as usize);
+        self.initial_state.encode(&mut e);
+
+        e.label("actions");
+        e.count(self.actions.len());
+        for (tick, action) in &self.actions {
+            e.label("action");
+            e.count(*tick as usize);
+            action.encode(&mut e);
+        }
+        writer.write_all(e.finish().as_bytes())?;
+        Ok(())
+    }
+
+    /// # Errors
+    /// If reading fails or the data isn't a valid recording of a supported version.
+    pub fn read(mut reader: impl Read) -> Result<Self, SnapshotError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let (mut d, version) = TextDecoder::new(&text, TEXT_HEADER)?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        d.label("tick")?;
+        let tick = d.float()?;
+        d.label("substeps")?;
+        let substeps = d.count()?;
+        // the same values ``SimulationClock::new`` rejects
+        if !(tick > 0.0 && tick.is_finite()) {
+            return Err(malformed(format!("tick {tick} is not positive")));
+        }
+        if substeps == 0 {
+            return Err(malformed("zero substeps"));
+        }
+        d.label("ticks")?;
+        let ticks = d.count()? as u64;
+
+        d.label("initial_state")?;
+        let snapshot_version = d.count()?;
+        if snapshot_version != SNAPSHOT_VERSION as usize {
+            return Err(SnapshotError::UnsupportedVersion(
+                u32::try_from(snapshot_version).unwrap_or(u32::MAX),
+            ));
+        }
+        let initial_state = WorldSnapshot::decode(&mut d)?;
+
+        d.label("actions")?;
+        let mut actions: Vec<(u64, Action)> = Vec::new();
+        for _ in 0..d.count()? {
+            d.label("action")?;
+            let tick = d.count()? as u64;
+            if actions.last().is_some_and(|(previous, _)| *previous > tick) || tick > ticks {
+                return Err(malformed(format!("action at tick {tick} is out of order")));
+            }
+            actions.push((tick, Action::decode(&mut d)?));
+        }
+        d.finish()?;
+
+        Ok(Self {
+            tick,
+            substeps,
+            ticks,
+            initial_state,
+            actions,
+        })
+    }
+
+    /// # Errors
+    /// If writing the file fails.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// # Errors
+    /// If the file can't be read or isn't a valid recording.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        Self::read(File::open(path)?)
+    }
+}
+
+/// Logs actions applied to a world driven by a ``SimulationClock``.
+pub struct Recorder {
+    recording: Recording,
+    first_tick: u64,
+}
+impl Recorder {
+    /// Starts recording ``world`` as it is now. Emitters and soft bodies aren't part of
+    /// snapshots, adding them after the start breaks the replay too.
+    ///
+    /// # Errors
+    /// If the world has emitters or soft bodies, or can't be saved, see ``World::snapshot``.
+    pub fn start(world: &World, clock: &SimulationClock) -> Result<Self, SnapshotError> {
+        if !world.emitters().is_empty() {
+            return Err(SnapshotError::Unrecordable("emitters"));
+        }
+        if !world.soft_bodies().is_empty() {
+            return Err(SnapshotError::Unrecordable("soft bodies"));
+        }
+        Ok(Self {
+            recording: Recording {
+                tick: clock.tick(),
+                substeps: clock.substeps(),
+                ticks: 0,
+                initial_state: world.snapshot()?,
+                actions: Vec::new(),
+            },
+            first_tick: clock.ticks(),
+        })
+    }
+
+    /// Applies ``action`` to ``world`` and logs it before the next tick of ``clock``.
+    pub fn apply(&mut self, world: &mut World, clock: &SimulationClock, action: Action) {
+        action.apply(world);
+        self.recording
+            .actions
+            .push((clock.ticks() - self.first_tick, action));
+    }
+
+    #[must_use]
+    pub fn finish(mut self, clock: &SimulationClock) -> Recording {
+        self.recording.ticks = clock.ticks() - self.first_tick;
+        self.recording
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec2;
+
+    use super::*;
+    use crate::engine2::emitter::Emitter;
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn replay_matches_live_world() {
+        let mut world = World::new();
+        let mut clock = SimulationClock::new(FRAME, 8);
+        let mut recorder = Recorder::start(&world, &clock).unwrap();
+
+        for frame in 0..120_u16 {
+            let x = f32::from(frame % 12).mul_add(0.1, -0.55);
+            if frame % 3 == 0 {
+                recorder.apply(
+                    &mut world,
+                    &clock,
+                    Action::Spawn {
+                        center: vec2(x, 0.6),
+                        radius: 0.03,
+                    },
+                );
+            }
+            if frame == 60 {
+                recorder.apply(
+                    &mut world,
+                    &clock,
+                    Action::SetContainer(Container::circle(vec2(0.0, 0.0), 0.85)),
+                );
+            }
+            if frame % 40 == 39 {
+                recorder.apply(
+                    &mut world,
+                    &clock,
+                    Action::Erase {
+                        center: vec2(0.0, -0.7),
+                        radius: 0.2,
+                    },
+                );
+            }
+            clock.advance(&mut world, FRAME);
+        }
+        let recording = recorder.finish(&clock);
+        assert_eq!(recording.ticks, 120);
+        assert!(!world.objects().is_empty());
+
+        assert_eq!(recording.replay().state_hash(), world.state_hash());
+
+        let mut data = Vec::new();
+        recording.write(&mut data).unwrap();
+        let read = Recording::read(data.as_slice()).unwrap();
+        assert_eq!(read.replay().state_hash(), world.state_hash());
+    }
+
+    #[test]
+    fn invalid_timing_is_rejected() {
+        let world = World::new();
+        let clock = SimulationClock::new(FRAME, 8);
+        let recording = Recorder::start(&world, &clock).unwrap().finish(&clock);
+
+        for (tick, substeps) in [(0.0, 8), (-FRAME, 8), (f32::NAN, 8), (FRAME, 0)] {
+            let mut data = Vec::new();
+            Recording {
+                tick,
+                substeps,
+                ..recording.clone()
+            }
+            .write(&mut data)
+            .unwrap();
+            assert!(matches!(
+                Recording::read(data.as_slice()),
+                Err(SnapshotError::Malformed(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn emitters_are_unrecordable() {
+        let mut world = World::new();
+        world.add_emitter(Emitter::new(vec2(0.0, 0.0), vec2(0.0, 1.0), 1.0, 10.0));
+        let clock = SimulationClock::new(FRAME, 8);
+        assert!(matches!(
+            Recorder::start(&world, &clock),
+            Err(SnapshotError::Unrecordable(_))
+        ));
+    }
+}
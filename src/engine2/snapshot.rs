@@ -0,0 +1,735 @@
+use std::{
+    error::Error,
+    fmt::{self, Write as _},
+    io::{self, Read, Write},
+    str::SplitWhitespace,
+};
+
+use nalgebra_glm::{vec2, Vec2};
+
+use crate::engine2::{
+    container::{check_polygon, ContactMaterial, Container},
+    forces::{GravityZone, LinearDrag, PointAttractor, SavedForce, UniformGravity},
+    links::{Link, LinkKind},
+    verlet_object::VerletObject,
+};
+
+/// Version written into new snapshots, bumped whenever the layout changes.
+pub const VERSION: u32 = 1;
+
+const TEXT_HEADER: &str = "engine2-snapshot";
+const BINARY_MAGIC: &[u8; 4] = b"E2SB";
+/// Keeps decoding of hostile data from overflowing the stack.
+const MAX_CONTAINER_DEPTH: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotFormat {
+    /// One record per line, floats printed in their shortest exact form.
+    Text,
+    /// Little-endian floats and counts.
+    Binary,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// The data starts with neither of the snapshot headers.
+    UnknownFormat,
+    UnsupportedVersion(u32),
+    Malformed(String),
+    /// The force generator at this index doesn't support saving.
+    UnsavableForce(usize),
+    /// A count too large for the binary format's 32 bits.
+    CountTooLarge(usize),
+    /// The world holds state snapshots leave out, e.g. emitters, so a recording of it
+    /// wouldn't replay the same.
+    Unrecordable(&'static str),
+}
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "snapshot i/o failed: {error}"),
+            Self::UnknownFormat => write!(f, "data is not a world snapshot"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "snapshot version {version} is not supported")
+            }
+            Self::Malformed(reason) => write!(f, "malformed snapshot: {reason}"),
+            Self::UnsavableForce(force_idx) => {
+                write!(f, "force generator {force_idx} can't be saved")
+            }
+            Self::CountTooLarge(count) => {
+                write!(f, "count {count} doesn't fit in a binary snapshot")
+            }
+            Self::Unrecordable(what) => write!(f, "worlds with {what} can't be recorded"),
+        }
+    }
+}
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Complete physics state of a ``World``, see ``World::snapshot`` and ``World::restore``.
+/// Links refer to objects by their index in ``objects``.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorldSnapshot {
+    pub objects: Vec<VerletObject>,
+    pub forces: Vec<SavedForce>,
+    pub container: Container,
+    pub container_material: ContactMaterial,
+    pub links: Vec<Link>,
+    pub kill_region: Option<Container>,
+}
+impl WorldSnapshot {
+    /// # Errors
+    /// If writing fails.
+    pub fn write(
+        &self,
+        mut writer: impl Write,
+        format: SnapshotFormat,
+    ) -> Result<(), SnapshotError> {
+        match format {
+            SnapshotFormat::Text => {
+                let mut encoder = TextEncoder::new(TEXT_HEADER, VERSION);
+                self.encode(&mut encoder);
+                writer.write_all(encoder.finish().as_bytes())?;
+            }
+            SnapshotFormat::Binary => {
+                let mut encoder = BinaryEncoder {
+                    out: BINARY_MAGIC.to_vec(),
+                    too_large: None,
+                };
+                encoder.out.extend(VERSION.to_le_bytes());
+                self.encode(&mut encoder);
+                if let Some(count) = encoder.too_large {
+                    return Err(SnapshotError::CountTooLarge(count));
+                }
+                writer.write_all(&encoder.out)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a snapshot in either format, the format is detected from the header.
+    ///
+    /// # Errors
+    /// If reading fails or the data isn't a valid snapshot of a supported version.
+    pub fn read(mut reader: impl Read) -> Result<Self, SnapshotError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        if let Some(bytes) = data.strip_prefix(BINARY_MAGIC) {
+            let mut decoder = BinaryDecoder { bytes };
+            let version = decoder.u32()?;
+            if version != VERSION {
+                return Err(SnapshotError::UnsupportedVersion(version));
+            }
+            let snapshot = Self::decode(&mut decoder)?;
+            if !decoder.bytes.is_empty() {
+                return Err(malformed("trailing bytes"));
+            }
+            return Ok(snapshot);
+        }
+
+        let text = std::str::from_utf8(&data).map_err(|_| SnapshotError::UnknownFormat)?;
+        let (mut decoder, version) = TextDecoder::new(text, TEXT_HEADER)?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let snapshot = Self::decode(&mut decoder)?;
+        decoder.finish()?;
+        Ok(snapshot)
+    }
+
+    pub(crate) fn encode(&self, e: &mut dyn Encoder) {
+        e.label("container");
+        encode_container(e, &self.container);
+        e.label("material");
+        e.float(self.container_material.restitution);
+        e.float(self.container_material.friction);
+        e.label("kill_region");
+        match &self.kill_region {
+            None => e.variant(OPTION, 0),
+            Some(region) => {
+                e.variant(OPTION, 1);
+                encode_container(e, region);
+            }
+        }
+
+        e.label("forces");
+        e.count(self.forces.len());
+        for force in &self.forces {
+            e.label("force");
+            encode_force(e, force);
+        }
+
+        e.label("objects");
+        e.count(self.objects.len());
+        for obj in &self.objects {
+            e.label("object");
+            encode_object(e, obj);
+        }
+
+        e.label("links");
+        e.count(self.links.len());
+        for link in &self.links {
+            e.label("link");
+            e.count(link.a);
+            e.count(link.b);
+            e.float(link.rest_length);
+            match link.kind {
+                LinkKind::Rigid => e.variant(LINK_KINDS, 0),
+                LinkKind::Spring { stiffness, damping } => {
+                    e.variant(LINK_KINDS, 1);
+                    e.float(stiffness);
+                    e.float(damping);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn decode(d: &mut dyn Decoder) -> Result<Self, SnapshotError> {
+        d.label("container")?;
+        let container = decode_container(d)?;
+        d.label("material")?;
+        let container_material = ContactMaterial::new(d.float()?, d.float()?);
+        d.label("kill_region")?;
+        let kill_region = match d.variant(OPTION)? {
+            0 => None,
+            _ => Some(decode_container(d)?),
+        };
+
+        d.label("forces")?;
+        let forces = (0..d.count()?)
+            .map(|_| {
+                d.label("force")?;
+                decode_force(d)
+            })
+            .collect::<Result<_, _>>()?;
+
+        d.label("objects")?;
+        let objects: Vec<VerletObject> = (0..d.count()?)
+            .map(|_| {
+                d.label("object")?;
+                decode_object(d)
+            })
+            .collect::<Result<_, _>>()?;
+
+        d.label("links")?;
+        let links = (0..d.count()?)
+            .map(|_| {
+                d.label("link")?;
+                let (a, b, rest_length) = (d.count()?, d.count()?, d.float()?);
+                if a == b || a >= objects.len() || b >= objects.len() {
+                    return Err(malformed(format!("link between {a} and {b} is invalid")));
+                }
+                Ok(match d.variant(LINK_KINDS)? {
+                    0 => Link::rigid(a, b, rest_length),
+                    _ => Link::spring(a, b, rest_length, d.float()?, d.float()?),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            objects,
+            forces,
+            container,
+            container_material,
+            links,
+            kill_region,
+        })
+    }
+}
+
+pub(crate) const OPTION: &[&str] = &["none", "some"];
+const LINK_KINDS: &[&str] = &["rigid", "spring"];
+const CONTAINERS: &[&str] = &[
+    "circle",
+    "rectangle",
+    "polygon",
+    "capsule",
+    "union",
+    "carved",
+];
+const FORCES: &[&str] = &[
+    "uniform_gravity",
+    "point_attractor",
+    "linear_drag",
+    "gravity_zone",
+];
+
+pub(crate) fn encode_vec2(e: &mut dyn Encoder, v: Vec2) {
+    e.float(v.x);
+    e.float(v.y);
+}
+
+pub(crate) fn decode_vec2(d: &mut dyn Decoder) -> Result<Vec2, SnapshotError> {
+    Ok(vec2(d.float()?, d.float()?))
+}
+
+pub(crate) fn encode_container(e: &mut dyn Encoder, container: &Container) {
+    match container {
+        Container::Circle { center, radius } => {
+            e.variant(CONTAINERS, 0);
+            encode_vec2(e, *center);
+            e.float(*radius);
+        }
+        Container::Rectangle {
+            center,
+            width,
+            height,
+        } => {
+            e.variant(CONTAINERS, 1);
+            encode_vec2(e, *center);
+            e.float(*width);
+            e.float(*height);
+        }
+        Container::Polygon { vertices } => {
+            e.variant(CONTAINERS, 2);
+            e.count(vertices.len());
+            for &vertex in vertices {
+                encode_vec2(e, vertex);
+            }
+        }
+        Container::Capsule { a, b, radius } => {
+            e.variant(CONTAINERS, 3);
+            encode_vec2(e, *a);
+            encode_vec2(e, *b);
+            e.float(*radius);
+        }
+        Container::Union(members) => {
+            e.variant(CONTAINERS, 4);
+            e.count(members.len());
+            for member in members {
+                encode_container(e, member);
+            }
+        }
+        Container::Carved { outer, obstacle } => {
+            e.variant(CONTAINERS, 5);
+            encode_container(e, outer);
+            encode_container(e, obstacle);
+        }
+    }
+}
+
+pub(crate) fn decode_container(d: &mut dyn Decoder) -> Result<Container, SnapshotError> {
+    decode_nested_container(d, 0)
+}
+
+fn decode_nested_container(d: &mut dyn Decoder, depth: usize) -> Result<Container, SnapshotError> {
+    if depth > MAX_CONTAINER_DEPTH {
+        return Err(malformed("containers nested too deep"));
+    }
+    // polygons are checked but kept as they are, going through ``Container::polygon``
+    // could reorder their vertices
+    Ok(match d.variant(CONTAINERS)? {
+        0 => Container::Circle {
+            center: decode_vec2(d)?,
+            radius: d.float()?,
+        },
+        1 => Container::Rectangle {
+            center: decode_vec2(d)?,
+            width: d.float()?,
+            height: d.float()?,
+        },
+        2 => {
+            let vertices: Vec<Vec2> = (0..d.count()?)
+                .map(|_| decode_vec2(d))
+                .collect::<Result<_, _>>()?;
+            check_polygon(&vertices)
+                .map_err(|reason| malformed(format!("invalid polygon, {reason}")))?;
+            Container::Polygon { vertices }
+        }
+        3 => Container::Capsule {
+            a: decode_vec2(d)?,
+            b: decode_vec2(d)?,
+            radius: d.float()?,
+        },
+        4 => Container::Union(
+            (0..d.count()?)
+                .map(|_| decode_nested_container(d, depth + 1))
+                .collect::<Result<_, _>>()?,
+        ),
+        _ => Container::Carved {
+            outer: Box::new(decode_nested_container(d, depth + 1)?),
+            obstacle: Box::new(decode_nested_container(d, depth + 1)?),
+        },
+    })
+}
+
+pub(crate) fn encode_force(e: &mut dyn Encoder, force: &SavedForce) {
+    match force {
+        SavedForce::UniformGravity(gravity) => {
+            e.variant(FORCES, 0);
+            encode_vec2(e, gravity.acceleration);
+        }
+        SavedForce::PointAttractor(attractor) => {
+            e.variant(FORCES, 1);
+            encode_vec2(e, attractor.center);
+            e.float(attractor.strength);
+            e.float(attractor.falloff);
+            e.float(attractor.min_distance);
+            e.float(attractor.range);
+        }
+        SavedForce::LinearDrag(drag) => {
+            e.variant(FORCES, 2);
+            e.float(drag.coefficient);
+        }
+        SavedForce::GravityZone(zone) => {
+            e.variant(FORCES, 3);
+            encode_container(e, &zone.region);
+            encode_vec2(e, zone.acceleration);
+        }
+    }
+}
+
+pub(crate) fn decode_force(d: &mut dyn Decoder) -> Result<SavedForce, SnapshotError> {
+    Ok(match d.variant(FORCES)? {
+        0 => SavedForce::UniformGravity(UniformGravity::new(decode_vec2(d)?)),
+        1 => SavedForce::PointAttractor(PointAttractor {
+            center: decode_vec2(d)?,
+            strength: d.float()?,
+            falloff: d.float()?,
+            min_distance: d.float()?,
+            range: d.float()?,
+        }),
+        2 => SavedForce::LinearDrag(LinearDrag::new(d.float()?)),
+        _ => SavedForce::GravityZone(GravityZone::new(decode_container(d)?, decode_vec2(d)?)),
+    })
+}
+
+fn encode_object(e: &mut dyn Encoder, obj: &VerletObject) {
+    encode_vec2(e, obj.get_center());
+    encode_vec2(e, obj.get_previous_center());
+    e.float(obj.get_radius());
+    encode_vec2(e, obj.get_acceleration());
+    obj.get_color().iter().for_each(|&channel| e.float(channel));
+    e.float(obj.get_inverse_mass());
+    match obj.get_lifetime() {
+        None => e.variant(OPTION, 0),
+        Some(lifetime) => {
+            e.variant(OPTION, 1);
+            e.float(lifetime);
+        }
+    }
+}
+
+fn decode_object(d: &mut dyn Decoder) -> Result<VerletObject, SnapshotError> {
+    let position = decode_vec2(d)?;
+    let previous_position = decode_vec2(d)?;
+    let radius = d.float()?;
+    let acceleration = decode_vec2(d)?;
+    let color = [d.float()?, d.float()?, d.float()?, d.float()?];
+    let inverse_mass = d.float()?;
+    let lifetime = match d.variant(OPTION)? {
+        0 => None,
+        _ => Some(d.float()?),
+    };
+    Ok(VerletObject::from_parts(
+        position,
+        previous_position,
+        radius,
+        acceleration,
+        color,
+        inverse_mass,
+        lifetime,
+    ))
+}
+
+pub(crate) fn malformed(reason: impl Into<String>) -> SnapshotError {
+    SnapshotError::Malformed(reason.into())
+}
+
+/// Both formats share the record layout, the text one additionally labels every record.
+pub(crate) trait Encoder {
+    fn label(&mut self, label: &str);
+    fn variant(&mut self, names: &[&str], variant_idx: usize);
+    fn count(&mut self, count: usize);
+    fn float(&mut self, value: f32);
+}
+
+pub(crate) trait Decoder {
+    fn label(&mut self, label: &str) -> Result<(), SnapshotError>;
+    fn variant(&mut self, names: &[&str]) -> Result<usize, SnapshotError>;
+    fn count(&mut self) -> Result<usize, SnapshotError>;
+    fn float(&mut self) -> Result<f32, SnapshotError>;
+}
+
+pub(crate) struct TextEncoder {
+    out: String,
+}
+impl TextEncoder {
+    pub(crate) fn new(header: &str, version: u32) -> Self {
+        Self {
+            out: format!("{header} {version}"),
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> String {
+        self.out.push('\n');
+        self.out
+    }
+}
+impl Encoder for TextEncoder {
+    fn label(&mut self, label: &str) {
+        self.out.push('\n');
+        self.out.push_str(label);
+    }
+
+    fn variant(&mut self, names: &[&str], variant_idx: usize) {
+        self.out.push(' ');
+        self.out.push_str(names[variant_idx]);
+    }
+
+    fn count(&mut self, count: usize) {
+        // writing into a `String` can't fail
+        let _ = write!(self.out, " {count}");
+    }
+
+    fn float(&mut self, value: f32) {
+        // `Display` prints the shortest representation that parses back to the same bits
+        let _ = write!(self.out, " {value}");
+    }
+}
+
+pub(crate) struct TextDecoder<'a> {
+    tokens: SplitWhitespace<'a>,
+}
+impl<'a> TextDecoder<'a> {
+    /// Starts reading ``text`` that begins with ``header`` and returns the version that
+    /// follows it.
+    ///
+    /// # Errors
+    /// If the header is missing or the version isn't a number.
+    pub(crate) fn new(text: &'a str, header: &str) -> Result<(Self, u32), SnapshotError> {
+        let mut tokens = text.split_whitespace();
+        if tokens.next() != Some(header) {
+            return Err(SnapshotError::UnknownFormat);
+        }
+        let mut decoder = Self { tokens };
+        let version = decoder.token()?;
+        let version = version
+            .parse()
+            .map_err(|_| malformed(format!("invalid version {version:?}")))?;
+        Ok((decoder, version))
+    }
+
+    /// # Errors
+    /// If anything follows the last record.
+    pub(crate) fn finish(mut self) -> Result<(), SnapshotError> {
+        self.tokens.next().map_or(Ok(()), |token| {
+            Err(malformed(format!(
+                "unexpected {token:?} after the last record"
+            )))
+        })
+    }
+
+    fn token(&mut self) -> Result<&str, SnapshotError> {
+        self.tokens
+            .next()
+            .ok_or_else(|| malformed("unexpected end of data"))
+    }
+}
+impl Decoder for TextDecoder<'_> {
+    fn label(&mut self, label: &str) -> Result<(), SnapshotError> {
+        let token = self.token()?;
+        if token == label {
+            Ok(())
+        } else {
+            Err(malformed(format!("expected {label:?}, found {token:?}")))
+        }
+    }
+
+    fn variant(&mut self, names: &[&str]) -> Result<usize, SnapshotError> {
+        let token = self.token()?;
+        names
+            .iter()
+            .position(|&name| name == token)
+            .ok_or_else(|| malformed(format!("unknown variant {token:?}")))
+    }
+
+    fn count(&mut self) -> Result<usize, SnapshotError> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| malformed(format!("invalid count {token:?}")))
+    }
+
+    fn float(&mut self) -> Result<f32, SnapshotError> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| malformed(format!("invalid number {token:?}")))
+    }
+}
+
+struct BinaryEncoder {
+    out: Vec<u8>,
+    /// First count that didn't fit, checked once encoding is done.
+    too_large: Option<usize>,
+}
+impl Encoder for BinaryEncoder {
+    fn label(&mut self, _label: &str) {}
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn variant(&mut self, names: &[&str], variant_idx: usize) {
+        debug_assert!(u8::try_from(names.len()).is_ok());
+        self.out.push(variant_idx as u8);
+    }
+
+    fn count(&mut self, count: usize) {
+        let Ok(count_u32) = u32::try_from(count) else {
+            self.too_large.get_or_insert(count);
+            return;
+        };
+        self.out.extend(count_u32.to_le_bytes());
+    }
+
+    fn float(&mut self, value: f32) {
+        self.out.extend(value.to_le_bytes());
+    }
+}
+
+struct BinaryDecoder<'a> {
+    bytes: &'a [u8],
+}
+impl BinaryDecoder<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], SnapshotError> {
+        let Some((head, rest)) = self.bytes.split_first_chunk() else {
+            return Err(malformed("unexpected end of data"));
+        };
+        self.bytes = rest;
+        Ok(*head)
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+}
+impl Decoder for BinaryDecoder<'_> {
+    fn label(&mut self, _label: &str) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+
+    fn variant(&mut self, names: &[&str]) -> Result<usize, SnapshotError> {
+        let [variant_idx] = self.take()?;
+        let variant_idx = usize::from(variant_idx);
+        if variant_idx < names.len() {
+            Ok(variant_idx)
+        } else {
+            Err(malformed(format!("unknown variant {variant_idx}")))
+        }
+    }
+
+    fn count(&mut self) -> Result<usize, SnapshotError> {
+        let count = self.u32()?;
+        usize::try_from(count).map_err(|_| malformed(format!("count {count} is too large")))
+    }
+
+    fn float(&mut self) -> Result<f32, SnapshotError> {
+        Ok(f32::from_le_bytes(self.take()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine2::{forces::LinearDrag, world::World};
+
+    fn busy_world() -> World {
+        let mut world = World::new();
+        world.set_container(
+            Container::rectangle(vec2(0.0, 0.0), 1.6, 1.2)
+                .union(Container::circle(vec2(0.8, 0.0), 0.4))
+                .carve(Container::capsule(vec2(-0.1, 0.0), vec2(0.1, 0.0), 0.05)),
+        );
+        world.set_kill_region(Some(Container::polygon(vec![
+            vec2(-0.05, -0.6),
+            vec2(0.05, -0.6),
+            vec2(0.0, -0.55),
+        ])));
+        world.add_force(Box::new(LinearDrag::new(0.1)));
+        let a = world.add_obj_at(vec2(-0.3, 0.3), 0.03);
+        let b = world.add_obj_at(vec2(-0.22, 0.3), 0.03);
+        let c = world.add_obj_at(vec2(-0.14, 0.32), 0.04);
+        world.add_rigid_link(a, b).unwrap();
+        world.add_spring(b, c, 500.0, 5.0).unwrap();
+        for x in 0..20 {
+            #[allow(clippy::cast_precision_loss)]
+            world.add_obj_at(vec2((x as f32).mul_add(0.05, -0.5), 0.1), 0.02);
+        }
+        for _ in 0..30 {
+            world.update(1.0 / 60.0, 8);
+        }
+        world
+    }
+
+    fn round_trip(format: SnapshotFormat) {
+        let world = busy_world();
+        let snapshot = world.snapshot().unwrap();
+        let mut data = Vec::new();
+        snapshot.write(&mut data, format).unwrap();
+        let read = WorldSnapshot::read(data.as_slice()).unwrap();
+        assert_eq!(read, snapshot);
+
+        let mut restored = World::new();
+        restored.restore(read);
+        assert_eq!(restored.state_hash(), world.state_hash());
+    }
+
+    #[test]
+    fn text_round_trip() {
+        round_trip(SnapshotFormat::Text);
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        round_trip(SnapshotFormat::Binary);
+    }
+
+    #[test]
+    fn invalid_polygons_are_rejected() {
+        let mut world = World::new();
+        world.set_kill_region(Some(Container::Polygon {
+            vertices: vec![
+                vec2(0.0, 0.0),
+                vec2(0.1, 0.0),
+                vec2(0.1, 0.0),
+                vec2(0.0, 0.1),
+            ],
+        }));
+        let mut data = Vec::new();
+        world
+            .snapshot()
+            .unwrap()
+            .write(&mut data, SnapshotFormat::Text)
+            .unwrap();
+        assert!(matches!(
+            WorldSnapshot::read(data.as_slice()),
+            Err(SnapshotError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn deep_container_nesting_is_rejected() {
+        let mut data = BINARY_MAGIC.to_vec();
+        data.extend(VERSION.to_le_bytes());
+        for _ in 0..=MAX_CONTAINER_DEPTH {
+            data.push(4);
+            data.extend(1u32.to_le_bytes());
+        }
+        assert!(matches!(
+            WorldSnapshot::read(data.as_slice()),
+            Err(SnapshotError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,174 @@
+use std::f32::consts::{PI, TAU};
+
+use nalgebra_glm::{vec2, Vec2};
+
+use crate::engine2::{handle::ObjectHandle, verlet_object::VerletObject};
+
+// floor of the area used for pressure, so a crushed or inverted ring is pushed back out
+// instead of losing its pressure
+const MIN_AREA_FRACTION: f32 = 0.1;
+
+/// Identifies a soft body for as long as it exists, removing other bodies doesn't change
+/// it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SoftBodyId(pub(crate) u64);
+
+/// Closed ring of objects joined by springs and inflated by gas pressure.
+///
+/// Created by ``World::add_soft_body`` from a ``SoftBodyBuilder``, the ring objects are
+/// ordinary world objects and collide with everything else.
+pub struct SoftBody {
+    id: SoftBodyId,
+    members: Vec<ObjectHandle>,
+    /// Pressure times enclosed area, stays constant as the body is squashed (ideal gas at
+    /// constant temperature).
+    pub pressure: f32,
+    pub color: [f32; 4],
+    rest_area: f32,
+}
+impl SoftBody {
+    pub(crate) const fn new(
+        id: SoftBodyId,
+        members: Vec<ObjectHandle>,
+        pressure: f32,
+        color: [f32; 4],
+        rest_area: f32,
+    ) -> Self {
+        Self {
+            id,
+            members,
+            pressure,
+            color,
+            rest_area,
+        }
+    }
+
+    #[must_use]
+    pub const fn id(&self) -> SoftBodyId {
+        self.id
+    }
+
+    /// Ring objects in counter-clockwise order.
+    #[must_use]
+    pub fn members(&self) -> &[ObjectHandle] {
+        &self.members
+    }
+
+    /// Area enclosed by the ring when it was created.
+    #[must_use]
+    pub const fn rest_area(&self) -> f32 {
+        self.rest_area
+    }
+
+    /// Pushes every edge of the ring outwards with a force of ``pressure / area`` per unit
+    /// of its length. ``ring`` are the indices of the members in ``objects``.
+    pub(crate) fn apply_pressure(&self, objects: &mut [VerletObject], ring: &[usize]) {
+        let centers: Vec<Vec2> = ring.iter().map(|&i| objects[i].get_center()).collect();
+        let area = enclosed_area(&centers).max(self.rest_area * MIN_AREA_FRACTION);
+        if area <= f32::EPSILON {
+            return;
+        }
+        let pressure = self.pressure / area;
+
+        for (edge_idx, &a) in ring.iter().enumerate() {
+            let next_idx = (edge_idx + 1) % ring.len();
+            let b = ring[next_idx];
+            let edge = centers[next_idx] - centers[edge_idx];
+            // outward normal of a counter-clockwise edge scaled by its length
+            let force = vec2(edge.y, -edge.x) * (pressure / 2.0);
+            for obj_idx in [a, b] {
+                let obj = &mut objects[obj_idx];
+                obj.accelerate(force * obj.get_inverse_mass());
+            }
+        }
+    }
+}
+
+/// Parameters of a soft body, see ``World::add_soft_body``.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoftBodyBuilder {
+    pub center: Vec2,
+    pub radius: f32,
+    pub segments: usize,
+    /// Radius of the ring objects, by default neighbours just touch.
+    pub particle_radius: f32,
+    /// Spring constants relative to the mass of a ring object.
+    pub stiffness: f32,
+    pub damping: f32,
+    /// Gas pressure at the initial area, relative to the mass of the whole ring.
+    pub pressure: f32,
+    pub color: [f32; 4],
+}
+impl SoftBodyBuilder {
+    /// # Panics
+    /// If the ring has fewer than three segments.
+    #[must_use]
+    pub fn new(center: Vec2, radius: f32, segments: usize) -> Self {
+        assert!(segments >= 3, "Soft body needs at least three segments.");
+        #[allow(clippy::cast_precision_loss)]
+        let chord = 2.0 * radius * (PI / segments as f32).sin();
+        Self {
+            center,
+            radius,
+            segments,
+            particle_radius: chord / 2.0,
+            stiffness: 20000.0,
+            damping: 30.0,
+            pressure: 12.0,
+            color: [0.3, 0.6, 1.0, 1.0],
+        }
+    }
+
+    #[must_use]
+    pub const fn with_particle_radius(mut self, particle_radius: f32) -> Self {
+        self.particle_radius = particle_radius;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_springs(mut self, stiffness: f32, damping: f32) -> Self {
+        self.stiffness = stiffness;
+        self.damping = damping;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_pressure(mut self, pressure: f32) -> Self {
+        self.pressure = pressure;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Ring objects in counter-clockwise order.
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn objects(&self) -> Vec<VerletObject> {
+        (0..self.segments)
+            .map(|segment| {
+                let angle = TAU * segment as f32 / self.segments as f32;
+                let offset = vec2(angle.cos(), angle.sin()) * self.radius;
+                VerletObject::new(self.center + offset, self.particle_radius, self.color)
+            })
+            .collect()
+    }
+}
+
+/// Shoelace formula, positive for counter-clockwise rings.
+#[must_use]
+pub fn enclosed_area(ring: &[Vec2]) -> f32 {
+    let Some(&last) = ring.last() else {
+        return 0.0;
+    };
+    let mut previous = last;
+    let mut doubled_area = 0.0;
+    for &vertex in ring {
+        doubled_area += previous.x.mul_add(vertex.y, -(vertex.x * previous.y));
+        previous = vertex;
+    }
+    doubled_area / 2.0
+}
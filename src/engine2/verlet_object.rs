@@ -1,16 +1,25 @@
+use std::f32::consts::PI;
+
 use nalgebra_glm::Vec2;
 
 const VEC2_ZERO: Vec2 = Vec2::new(0.0, 0.0);
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct VerletObject {
     position: Vec2,
     previous_position: Vec2,
     radius: f32,
     acceleration: Vec2,
     color: [f32; 4],
+    // zero makes the object static
+    inverse_mass: f32,
+    // seconds left before the object gets despawned
+    lifetime: Option<f32>,
 }
 
 impl VerletObject {
+    /// Dynamic object of unit density.
+    #[must_use]
     pub const fn new(position: Vec2, radius: f32, color: [f32; 4]) -> Self {
         Self {
             position,
@@ -18,13 +27,104 @@ impl VerletObject {
             previous_position: position,
             acceleration: VEC2_ZERO,
             color,
+            inverse_mass: 1.0 / (PI * radius * radius),
+            lifetime: None,
+        }
+    }
+
+    /// Rebuilds an object field by field, used when loading snapshots.
+    pub(crate) const fn from_parts(
+        position: Vec2,
+        previous_position: Vec2,
+        radius: f32,
+        acceleration: Vec2,
+        color: [f32; 4],
+        inverse_mass: f32,
+        lifetime: Option<f32>,
+    ) -> Self {
+        Self {
+            position,
+            previous_position,
+            radius,
+            acceleration,
+            color,
+            inverse_mass,
+            lifetime,
         }
     }
 
+    /// Object despawned by the ``World`` after ``seconds``.
+    #[must_use]
+    pub const fn with_lifetime(mut self, seconds: f32) -> Self {
+        self.lifetime = Some(seconds);
+        self
+    }
+
+    /// Object launched at ``velocity``, see ``set_velocity``.
+    #[must_use]
+    pub fn with_velocity(mut self, velocity: Vec2, dt: f32) -> Self {
+        self.set_velocity(velocity, dt);
+        self
+    }
+
+    #[must_use]
+    pub fn with_mass(mut self, mass: f32) -> Self {
+        self.set_mass(mass);
+        self
+    }
+
+    #[must_use]
+    pub fn with_density(self, density: f32) -> Self {
+        let mass = density * PI * self.radius.powi(2);
+        self.with_mass(mass)
+    }
+
+    /// Static object, see ``set_static``.
+    #[must_use]
+    pub const fn pinned(mut self) -> Self {
+        self.inverse_mass = 0.0;
+        self
+    }
+
+    #[must_use]
     pub const fn get_center(&self) -> Vec2 {
         self.position
     }
 
+    #[must_use]
+    pub const fn get_previous_center(&self) -> Vec2 {
+        self.previous_position
+    }
+
+    /// Distance travelled during the last step, the implicit Verlet velocity times dt.
+    #[must_use]
+    pub fn get_displacement(&self) -> Vec2 {
+        self.position - self.previous_position
+    }
+
+    /// Rewrites ``previous_position`` so the next step travels by ``displacement``.
+    pub fn set_displacement(&mut self, displacement: Vec2) {
+        self.previous_position = self.position - displacement;
+    }
+
+    /// Velocity over the last solver step of length ``dt`` (the tick divided by the number
+    /// of substeps when stepped by a ``World``).
+    #[must_use]
+    pub fn get_velocity(&self, dt: f32) -> Vec2 {
+        self.get_displacement() / dt
+    }
+
+    /// Rewrites ``previous_position`` so the object moves at ``velocity`` during the next
+    /// solver step of length ``dt``.
+    pub fn set_velocity(&mut self, velocity: Vec2, dt: f32) {
+        self.set_displacement(velocity * dt);
+    }
+
+    /// Changes the velocity by ``impulse`` divided by the mass, static objects ignore it.
+    pub fn apply_impulse(&mut self, impulse: Vec2, dt: f32) {
+        self.previous_position -= impulse * (self.inverse_mass * dt);
+    }
+
     pub fn shift(&mut self, vector: Vec2) {
         self.position += vector;
     }
@@ -39,6 +139,11 @@ impl VerletObject {
     }
 
     pub fn update_position(&mut self, dt: f32) {
+        if self.is_static() {
+            self.previous_position = self.position;
+            self.acceleration = VEC2_ZERO;
+            return;
+        }
         let velocity_dt = self.position - self.previous_position;
         self.previous_position = self.position;
 
@@ -46,11 +151,18 @@ impl VerletObject {
         self.acceleration = VEC2_ZERO;
     }
 
+    #[must_use]
     pub fn collides_with(&self, other: &Self) -> bool {
         self.position.metric_distance(&other.position) < self.radius + other.radius
     }
 
-    pub fn set_acceleration(&mut self, acceleration: Vec2) {
+    /// Acceleration accumulated for the next integration.
+    #[must_use]
+    pub const fn get_acceleration(&self) -> Vec2 {
+        self.acceleration
+    }
+
+    pub const fn set_acceleration(&mut self, acceleration: Vec2) {
         self.acceleration = acceleration;
     }
 
@@ -58,10 +170,74 @@ impl VerletObject {
         self.acceleration += delta;
     }
 
+    #[must_use]
     pub const fn get_radius(&self) -> f32 {
         self.radius
     }
 
+    /// ``f32::INFINITY`` for static objects.
+    #[must_use]
+    pub fn get_mass(&self) -> f32 {
+        if self.is_static() {
+            f32::INFINITY
+        } else {
+            1.0 / self.inverse_mass
+        }
+    }
+
+    #[must_use]
+    pub const fn get_inverse_mass(&self) -> f32 {
+        self.inverse_mass
+    }
+
+    /// Infinite (or non-positive) mass makes the object static.
+    pub fn set_mass(&mut self, mass: f32) {
+        self.inverse_mass = if mass.is_finite() && mass > 0.0 {
+            1.0 / mass
+        } else {
+            0.0
+        };
+    }
+
+    /// Static objects are never moved by the solver, neither by integration nor by
+    /// collisions, links or the container. They can still be moved by hand.
+    #[must_use]
+    pub fn is_static(&self) -> bool {
+        self.inverse_mass == 0.0
+    }
+
+    /// Pins the object in place. Unpinning restores unit density mass.
+    pub fn set_static(&mut self, is_static: bool) {
+        self.inverse_mass = if is_static {
+            0.0
+        } else {
+            1.0 / (PI * self.radius.powi(2))
+        };
+    }
+
+    /// Seconds left to live, ``None`` for objects living forever.
+    #[must_use]
+    pub const fn get_lifetime(&self) -> Option<f32> {
+        self.lifetime
+    }
+
+    pub const fn set_lifetime(&mut self, lifetime: Option<f32>) {
+        self.lifetime = lifetime;
+    }
+
+    /// Shortens the remaining lifetime by ``dt`` seconds.
+    pub fn age(&mut self, dt: f32) {
+        if let Some(lifetime) = &mut self.lifetime {
+            *lifetime -= dt;
+        }
+    }
+
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.lifetime.is_some_and(|lifetime| lifetime <= 0.0)
+    }
+
+    #[must_use]
     pub const fn get_color(&self) -> [f32; 4] {
         self.color
     }
@@ -1,130 +1,521 @@
-use std::f32::consts::PI;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
 
 use glium::glutin::event_loop::EventLoop;
-use grid::Grid;
-use itertools::Itertools;
-use nalgebra_glm::{rotation2d, vec2, vec2_to_vec3, vec3, Vec2};
+use nalgebra_glm::{vec2, Vec2};
 
 use crate::engine2::{
-    graphics::Renderer, objects_generator::ObjectsGenerator, verlet_object::VerletObject,
+    broad_phase::{uniform_grid::UniformGrid, BroadPhase},
+    collision::{pair_mut, solve_collision, Penetration},
+    contacts::{Contact, ContactEvent, ContactTracker},
+    container::{ContactMaterial, Container},
+    emitter::Emitter,
+    forces::{ForceGenerator, SavedForce, UniformGravity},
+    graphics::{shape::Shape, Renderer},
+    handle::{HandleMap, ObjectHandle, StaleHandle},
+    links::{Link, LinkId, Links},
+    objects_generator::ObjectsGenerator,
+    parallel_solver::ParallelCollisionSolver,
+    query::SpatialQuery,
+    snapshot::{SnapshotError, SnapshotFormat, WorldSnapshot},
+    soft_body::{enclosed_area, SoftBody, SoftBodyBuilder, SoftBodyId},
+    verlet_object::VerletObject,
 };
 
 const WORLD_HEIGHT: u16 = 1000;
 const WORLD_WIDTH: u16 = 1000;
 
-const GRID_ROWS: u16 = 50;
-const GRID_COLS: u16 = 50;
-
-const ROW_HEIGHT: f32 = 2.0 / GRID_ROWS as f32;
-const COL_WIDTH: f32 = 2.0 / GRID_COLS as f32;
-
 const GRAVITY: Vec2 = Vec2::new(0.0, -1.0);
 
+/// Physics state of the simulation. Rendering is optional, a ``World`` created with
+/// ``World::new`` never touches windowing or OpenGL and can be stepped anywhere.
 pub struct World {
     objects: Vec<VerletObject>,
-    gravity: Vec2,
-    renderer: Renderer,
+    handles: HandleMap,
+    forces: Vec<Box<dyn ForceGenerator>>,
+    container: Container,
+    container_material: ContactMaterial,
+    broad_phase: Box<dyn BroadPhase>,
+    collision_pairs: Vec<(usize, usize)>,
+    parallel_solver: Option<ParallelCollisionSolver>,
+    contact_tracker: Option<ContactTracker>,
+    raw_contacts: Vec<(usize, usize, Penetration)>,
+    links: Links,
+    kill_region: Option<Container>,
+    emitters: Vec<Emitter>,
+    soft_bodies: Vec<SoftBody>,
+    // ids are never reused
+    next_soft_body_id: u64,
+    // positions at the start of the last tick, render interpolation blends from them
+    tick_start: Vec<Vec2>,
+    query_grid: UniformGrid,
+    query_grid_outdated: bool,
+    renderer: Option<Renderer>,
 }
 impl World {
-    pub fn new<T>(event_loop: &EventLoop<T>) -> Self {
-        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-        #[allow(clippy::cast_precision_loss)]
+    /// Creates a headless world.
+    #[must_use]
+    pub fn new() -> Self {
         Self {
             objects: Vec::new(),
-            gravity: GRAVITY,
-            renderer: Renderer::new(event_loop, WORLD_WIDTH as u32, WORLD_HEIGHT as u32),
+            handles: HandleMap::default(),
+            forces: vec![Box::new(UniformGravity::new(GRAVITY))],
+            container: Container::default(),
+            container_material: ContactMaterial::new(0.0, 0.0),
+            broad_phase: Box::new(UniformGrid::default()),
+            collision_pairs: Vec::new(),
+            parallel_solver: None,
+            contact_tracker: None,
+            raw_contacts: Vec::new(),
+            links: Links::default(),
+            kill_region: None,
+            emitters: Vec::new(),
+            soft_bodies: Vec::new(),
+            next_soft_body_id: 0,
+            tick_start: Vec::new(),
+            query_grid: UniformGrid::default(),
+            query_grid_outdated: true,
+            renderer: None,
         }
     }
 
+    /// Creates a world with a window and an OpenGL renderer attached.
+    pub fn with_renderer<T>(event_loop: &EventLoop<T>) -> Self {
+        let mut world = Self::new();
+        world.renderer = Some(Renderer::new(
+            event_loop,
+            u32::from(WORLD_WIDTH),
+            u32::from(WORLD_HEIGHT),
+        ));
+        world
+    }
+
+    pub const fn is_headless(&self) -> bool {
+        self.renderer.is_none()
+    }
+
+    #[allow(clippy::cast_precision_loss)]
     pub fn update(&mut self, dt: f32, substeps: usize) {
+        self.run_emitters(dt, dt / substeps as f32);
+        self.tick_start.clear();
+        self.tick_start
+            .extend(self.objects.iter().map(VerletObject::get_center));
         let dt = dt / substeps as f32;
+        if let Some(tracker) = &mut self.contact_tracker {
+            tracker.begin_step();
+        }
         for _ in 0..substeps {
-            self.apply_gravity();
+            self.apply_forces(dt);
+            self.links.apply_springs(&mut self.objects, dt);
+            self.apply_pressure();
 
             // TODO: determine the correct order of these two
-            self.constrain(Constraint::Rectangular);
+            self.constrain();
 
-            // self.solve_collisions();
-            self.solve_collisions_with_grid();
+            self.solve_collisions();
+            self.record_contacts(dt);
+            self.links.solve_rigid(&mut self.objects);
 
             self.update_positions(dt);
         }
+        if let Some(tracker) = &mut self.contact_tracker {
+            tracker.finish_step();
+        }
+        self.despawn(dt * substeps as f32);
+        self.query_grid_outdated = true;
+    }
+
+    fn run_emitters(&mut self, dt: f32, step_dt: f32) {
+        let spawned: Vec<VerletObject> = self
+            .emitters
+            .iter_mut()
+            .flat_map(|emitter| emitter.emit(dt, step_dt))
+            .collect();
+        if spawned.is_empty() {
+            return;
+        }
+        for obj in spawned {
+            self.push_object(obj);
+        }
+        self.resize_buffers();
+    }
+
+    /// Registers an emitter ticked at the start of every ``update``, returns its index.
+    pub fn add_emitter(&mut self, emitter: Emitter) -> usize {
+        self.emitters.push(emitter);
+        self.emitters.len() - 1
+    }
+
+    pub fn remove_emitter(&mut self, emitter_idx: usize) -> Emitter {
+        self.emitters.remove(emitter_idx)
+    }
+
+    pub fn emitters(&self) -> &[Emitter] {
+        &self.emitters
+    }
+
+    /// Emitters can be moved, re-aimed or paused between updates.
+    pub fn emitters_mut(&mut self) -> &mut [Emitter] {
+        &mut self.emitters
     }
 
+    /// Spawns a ring of objects joined by springs and returns the id of the soft body.
+    /// The body is dropped on the next ``update`` once any of its objects is removed.
+    ///
+    /// # Panics
+    /// If ``builder.segments`` is below three, see ``SoftBodyBuilder::new``.
     #[allow(clippy::cast_precision_loss)]
-    pub fn populate(&mut self, generator: &mut ObjectsGenerator) {
-        let distance = (generator.obj_radius + generator.obj_radius_deviation)
-            .mul_add(2.0, generator.obj_min_separation);
-        let x = -(generator.grid_columns as f32 / 2.0) * distance;
-        let mut y = (generator.grid_rows as f32 / 2.0) * distance;
-
-        for _row in 0..generator.grid_rows {
-            for col in 0..generator.grid_columns {
-                let temp_x = distance.mul_add(col as f32, x);
-                let center = rotation2d(generator.grid_rotation) * vec3(temp_x, y, 1.0)
-                    + vec2_to_vec3(&generator.grid_center);
-
-                let radius = generator.random_radius();
-
-                self.objects
-                    .push(VerletObject::new(center.xy(), radius, [1.0, 1.0, 1.0, 1.0]));
-            }
-            y -= distance;
+    pub fn add_soft_body(&mut self, builder: &SoftBodyBuilder) -> SoftBodyId {
+        assert!(
+            builder.segments >= 3,
+            "Soft body needs at least three segments."
+        );
+        let objects = builder.objects();
+        let mass = objects[0].get_mass();
+        let area = enclosed_area(
+            &objects
+                .iter()
+                .map(VerletObject::get_center)
+                .collect::<Vec<_>>(),
+        );
+        let first = self.objects.len();
+        let members: Vec<ObjectHandle> = objects
+            .into_iter()
+            .map(|obj| self.add_object(obj))
+            .collect();
+
+        for member_idx in 0..members.len() {
+            let (a, b) = (first + member_idx, first + (member_idx + 1) % members.len());
+            let rest_length = self.objects[a]
+                .get_center()
+                .metric_distance(&self.objects[b].get_center());
+            self.links.add(Link::spring(
+                a,
+                b,
+                rest_length,
+                builder.stiffness * mass,
+                builder.damping * mass,
+            ));
         }
-        self.renderer.rewrite_vertex_buffer(&self.objects);
-        self.renderer.rewrite_index_buffer(&self.objects);
+        let ring_mass = mass * members.len() as f32;
+        let id = SoftBodyId(self.next_soft_body_id);
+        self.next_soft_body_id += 1;
+        self.soft_bodies.push(SoftBody::new(
+            id,
+            members,
+            builder.pressure * ring_mass * area,
+            builder.color,
+            area,
+        ));
+        id
     }
 
-    fn constrain(&mut self, constraint: Constraint) {
-        self.objects.iter_mut().for_each(|obj| {
-            if let Some(offset) = Self::trespass_vector(obj, constraint) {
-                // obj.adjust_position_data(-offset);
-                obj.shift(-offset);
-            }
+    /// Stops inflating the body, its objects and springs stay in the world. ``None`` if
+    /// the body is already gone.
+    pub fn remove_soft_body(&mut self, id: SoftBodyId) -> Option<SoftBody> {
+        let body_idx = self.soft_bodies.iter().position(|body| body.id() == id)?;
+        Some(self.soft_bodies.remove(body_idx))
+    }
+
+    #[must_use]
+    pub fn soft_body(&self, id: SoftBodyId) -> Option<&SoftBody> {
+        self.soft_bodies.iter().find(|body| body.id() == id)
+    }
+
+    pub fn soft_body_mut(&mut self, id: SoftBodyId) -> Option<&mut SoftBody> {
+        self.soft_bodies.iter_mut().find(|body| body.id() == id)
+    }
+
+    pub fn soft_bodies(&self) -> &[SoftBody] {
+        &self.soft_bodies
+    }
+
+    pub fn soft_bodies_mut(&mut self) -> &mut [SoftBody] {
+        &mut self.soft_bodies
+    }
+
+    fn apply_pressure(&mut self) {
+        let handles = &self.handles;
+        self.soft_bodies.retain(|body| {
+            body.members()
+                .iter()
+                .all(|&member| handles.index_of(member).is_ok())
         });
+        for body in &self.soft_bodies {
+            let ring: Vec<usize> = body
+                .members()
+                .iter()
+                .filter_map(|&member| handles.index_of(member).ok())
+                .collect();
+            body.apply_pressure(&mut self.objects, &ring);
+        }
     }
 
-    // TODO: is this the best way? it feels like a brute force.
-    fn trespass_vector(obj: &VerletObject, constraint: Constraint) -> Option<Vec2> {
-        match constraint {
-            Constraint::Circular => {
-                const CONSTRAINT_CENTER: Vec2 = Vec2::new(0.0, 0.0);
-                const CONSTRAINT_RADIUS: f32 = 0.9;
-
-                let distance_from_center = obj.get_center().metric_distance(&CONSTRAINT_CENTER);
-                if distance_from_center + obj.get_radius() > CONSTRAINT_RADIUS {
-                    let distance_vec = obj.get_center() - CONSTRAINT_CENTER;
-                    let radius_vec =
-                        distance_vec.normalize() * (CONSTRAINT_RADIUS - obj.get_radius());
-                    let trespass_vec = distance_vec - radius_vec;
-                    Some(trespass_vec)
-                } else {
-                    None
-                }
+    /// Removes objects that outlived their lifetime or left the kill region.
+    fn despawn(&mut self, dt: f32) {
+        let mut removed_any = false;
+        for obj_idx in (0..self.objects.len()).rev() {
+            let obj = &mut self.objects[obj_idx];
+            obj.age(dt);
+            let outside = self
+                .kill_region
+                .as_ref()
+                .is_some_and(|region| region.signed_distance(obj.get_center()).0 > 0.0);
+            if obj.is_expired() || outside {
+                self.swap_remove(obj_idx);
+                removed_any = true;
             }
-            Constraint::Rectangular => {
-                const CONSTRAINT_BOUND: f32 = 0.9;
+        }
+        if removed_any {
+            self.resize_buffers();
+        }
+    }
 
-                let max_center = CONSTRAINT_BOUND - obj.get_radius();
+    fn swap_remove(&mut self, obj_idx: usize) -> VerletObject {
+        self.query_grid_outdated = true;
+        let last = self.objects.len() - 1;
+        self.links.on_swap_remove(obj_idx, last);
+        self.handles.swap_remove(obj_idx);
+        self.tick_start.swap_remove(obj_idx);
+        self.objects.swap_remove(obj_idx)
+    }
 
-                let clamped = vec2(
-                    obj.get_center().x.clamp(-max_center, max_center),
-                    obj.get_center().y.clamp(-max_center, max_center),
-                );
+    /// Removes the object together with the links attached to it. The last object takes
+    /// the index of the removed one, handles stay valid.
+    ///
+    /// # Errors
+    /// If the object has already been removed.
+    pub fn remove_object(&mut self, handle: ObjectHandle) -> Result<VerletObject, StaleHandle> {
+        let obj_idx = self.handles.index_of(handle)?;
+        let removed = self.swap_remove(obj_idx);
+        self.resize_buffers();
+        Ok(removed)
+    }
 
-                if clamped == obj.get_center() {
-                    None
-                } else {
-                    Some(obj.get_center() - clamped)
-                }
+    /// Current index of the object in ``objects()``.
+    ///
+    /// # Errors
+    /// If the object has been removed.
+    pub fn index_of(&self, handle: ObjectHandle) -> Result<usize, StaleHandle> {
+        self.handles.index_of(handle)
+    }
+
+    /// Handle of the object currently stored at ``obj_idx``.
+    pub fn handle_at(&self, obj_idx: usize) -> Option<ObjectHandle> {
+        self.handles.handle_at(obj_idx)
+    }
+
+    pub fn contains(&self, handle: ObjectHandle) -> bool {
+        self.handles.index_of(handle).is_ok()
+    }
+
+    /// # Errors
+    /// If the object has been removed.
+    pub fn get(&self, handle: ObjectHandle) -> Result<&VerletObject, StaleHandle> {
+        Ok(&self.objects[self.handles.index_of(handle)?])
+    }
+
+    /// # Errors
+    /// If the object has been removed.
+    pub fn get_mut(&mut self, handle: ObjectHandle) -> Result<&mut VerletObject, StaleHandle> {
+        self.query_grid_outdated = true;
+        Ok(&mut self.objects[self.handles.index_of(handle)?])
+    }
+
+    /// Objects whose centers leave ``region`` are despawned, ``None`` disables it.
+    pub fn set_kill_region(&mut self, region: Option<Container>) {
+        self.kill_region = region;
+    }
+
+    pub const fn kill_region(&self) -> Option<&Container> {
+        self.kill_region.as_ref()
+    }
+
+    /// Spawns objects at the positions given by the generator's layout.
+    pub fn populate(&mut self, generator: &mut ObjectsGenerator) -> Vec<ObjectHandle> {
+        let positions = generator.positions();
+        let mut handles = Vec::with_capacity(positions.len());
+        for position in positions {
+            let center = generator.jittered(position);
+            let radius = generator.random_radius();
+
+            handles.push(self.push_object(VerletObject::new(center, radius, [1.0, 1.0, 1.0, 1.0])));
+        }
+        self.resize_buffers();
+        handles
+    }
+
+    /// Copies the physics state: objects, forces, container, links and kill region.
+    /// Emitters, soft body pressure, contact tracking and the broad phase aren't included.
+    /// The broad phase decides the order collisions get resolved in, and sweep and prune
+    /// keeps its sort between ticks, so a restored world only follows the original
+    /// bit for bit when it uses a fresh broad phase of the same kind.
+    ///
+    /// # Errors
+    /// If one of the force generators can't be saved.
+    pub fn snapshot(&self) -> Result<WorldSnapshot, SnapshotError> {
+        let forces = self
+            .forces
+            .iter()
+            .enumerate()
+            .map(|(force_idx, force)| {
+                force
+                    .saved()
+                    .ok_or(SnapshotError::UnsavableForce(force_idx))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(WorldSnapshot {
+            objects: self.objects.clone(),
+            forces,
+            container: self.container.clone(),
+            container_material: self.container_material,
+            links: self.links.as_slice().to_vec(),
+            kill_region: self.kill_region.clone(),
+        })
+    }
+
+    /// Replaces the physics state with the snapshot. All existing handles go stale, the
+    /// restored objects get new ones in the order of ``snapshot.objects``.
+    pub fn restore(&mut self, snapshot: WorldSnapshot) -> Vec<ObjectHandle> {
+        for obj_idx in (0..self.objects.len()).rev() {
+            self.handles.swap_remove(obj_idx);
+        }
+        self.objects.clear();
+        self.tick_start.clear();
+        let handles = snapshot
+            .objects
+            .into_iter()
+            .map(|obj| self.push_object(obj))
+            .collect();
+        self.forces = snapshot
+            .forces
+            .into_iter()
+            .map(SavedForce::into_generator)
+            .collect();
+        self.container = snapshot.container;
+        self.container_material = snapshot.container_material;
+        self.links.clear();
+        for link in snapshot.links {
+            self.links.add(link);
+        }
+        self.kill_region = snapshot.kill_region;
+        if let Some(tracker) = &mut self.contact_tracker {
+            tracker.reset();
+        }
+        self.query_grid_outdated = true;
+        self.resize_buffers();
+        handles
+    }
+
+    /// FNV-1a hash of the bits of every object's state. Worlds with equal hashes almost
+    /// certainly hold bit-identical objects.
+    pub fn state_hash(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut hash = OFFSET_BASIS;
+        let mut write = |value: f32| {
+            for byte in value.to_bits().to_le_bytes() {
+                hash = (hash ^ u64::from(byte)).wrapping_mul(PRIME);
             }
+        };
+        for obj in &self.objects {
+            let values = [
+                obj.get_center().x,
+                obj.get_center().y,
+                obj.get_previous_center().x,
+                obj.get_previous_center().y,
+                obj.get_radius(),
+                obj.get_acceleration().x,
+                obj.get_acceleration().y,
+                obj.get_inverse_mass(),
+                obj.get_lifetime().unwrap_or(f32::NAN),
+            ];
+            values
+                .into_iter()
+                .chain(obj.get_color())
+                .for_each(&mut write);
         }
+        hash
     }
 
-    fn apply_gravity(&mut self) {
+    /// # Errors
+    /// If a force generator can't be saved or writing the file fails.
+    pub fn save(
+        &self,
+        path: impl AsRef<Path>,
+        format: SnapshotFormat,
+    ) -> Result<(), SnapshotError> {
+        let snapshot = self.snapshot()?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        snapshot.write(&mut writer, format)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Loads a snapshot saved in either format, the world is left untouched on failure.
+    ///
+    /// # Errors
+    /// If the file can't be read or isn't a valid snapshot.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<Vec<ObjectHandle>, SnapshotError> {
+        let snapshot = WorldSnapshot::read(File::open(path)?)?;
+        Ok(self.restore(snapshot))
+    }
+
+    fn constrain(&mut self) {
+        let container = &self.container;
+        let material = self.container_material;
         self.objects
             .iter_mut()
-            .for_each(|obj| obj.set_acceleration(self.gravity));
+            .filter(|obj| !obj.is_static())
+            .for_each(|obj| {
+                if let Some(contact) = container.contact(obj.get_center(), obj.get_radius()) {
+                    obj.adjust_position_data(contact.correction());
+                    obj.set_displacement(material.respond(obj.get_displacement(), contact.normal));
+                }
+            });
+    }
+
+    fn apply_forces(&mut self, dt: f32) {
+        for force in &self.forces {
+            force.apply(&mut self.objects, dt);
+        }
+    }
+
+    /// Registers a force generator, returns its index. A new world starts with a single
+    /// ``UniformGravity``.
+    pub fn add_force(&mut self, force: Box<dyn ForceGenerator>) -> usize {
+        self.forces.push(force);
+        self.forces.len() - 1
+    }
+
+    /// Force generators are applied in index order, removing one shifts the indices of all
+    /// registered after it down by one.
+    ///
+    /// # Panics
+    /// If ``force_idx`` is out of bounds.
+    pub fn remove_force(&mut self, force_idx: usize) -> Box<dyn ForceGenerator> {
+        self.forces.remove(force_idx)
+    }
+
+    pub fn forces(&self) -> &[Box<dyn ForceGenerator>] {
+        &self.forces
+    }
+
+    pub fn forces_mut(&mut self) -> &mut [Box<dyn ForceGenerator>] {
+        &mut self.forces
+    }
+
+    /// Removes all force generators, including gravity.
+    pub fn clear_forces(&mut self) {
+        self.forces.clear();
+    }
+
+    pub fn forces_number(&self) -> usize {
+        self.forces.len()
     }
 
     pub fn update_positions(&mut self, dt: f32) {
@@ -133,112 +524,366 @@ impl World {
         });
     }
 
-    pub fn add_obj_at(&mut self, center: Vec2, radius: f32) {
+    pub fn add_obj_at(&mut self, center: Vec2, radius: f32) -> ObjectHandle {
         let new_obj = VerletObject::new(center, radius, [1.0, 1.0, 1.0, 1.0]);
 
-        self.objects.push(new_obj);
+        self.add_object(new_obj)
+    }
+
+    /// Adds a fully configured object (e.g. with a custom mass or pinned).
+    pub fn add_object(&mut self, obj: VerletObject) -> ObjectHandle {
+        let handle = self.push_object(obj);
+        self.resize_buffers();
+        handle
+    }
 
-        self.renderer.rewrite_vertex_buffer(&self.objects);
-        self.renderer.rewrite_index_buffer(&self.objects);
+    // every new object goes through here to keep ``objects``, ``handles`` and
+    // ``tick_start`` aligned, callers resize the render buffers once they're done
+    fn push_object(&mut self, obj: VerletObject) -> ObjectHandle {
+        self.tick_start.push(obj.get_center());
+        self.objects.push(obj);
+        self.query_grid_outdated = true;
+        self.handles.push()
     }
 
+    #[allow(clippy::unused_self)]
     pub fn to_gl_coords(&self, physical_coords: Vec2) -> Vec2 {
-        let x = (physical_coords.x / WORLD_WIDTH as f32).mul_add(2.0, -1.0);
-        let y = (physical_coords.y / WORLD_HEIGHT as f32).mul_add(2.0, -1.0);
+        let x = (physical_coords.x / f32::from(WORLD_WIDTH)).mul_add(2.0, -1.0);
+        let y = (physical_coords.y / f32::from(WORLD_HEIGHT)).mul_add(2.0, -1.0);
 
         vec2(x, -y)
     }
 
-    pub fn objects_number(&self) -> usize {
+    /// Adds a link between two objects given by their current indices, returns its id.
+    ///
+    /// # Panics
+    /// If the link connects an object with itself or refers to an object that doesn't exist.
+    pub fn add_link(&mut self, link: Link) -> LinkId {
+        assert!(
+            link.a != link.b && link.a < self.objects.len() && link.b < self.objects.len(),
+            "Link has to connect two existing objects."
+        );
+        self.links.add(link)
+    }
+
+    /// Rigid link keeping the objects at their current distance.
+    ///
+    /// # Errors
+    /// If either of the objects has been removed.
+    ///
+    /// # Panics
+    /// If ``a`` and ``b`` are the same object.
+    pub fn add_rigid_link(
+        &mut self,
+        a: ObjectHandle,
+        b: ObjectHandle,
+    ) -> Result<LinkId, StaleHandle> {
+        let (a, b, length) = self.distance_between(a, b)?;
+        Ok(self.add_link(Link::rigid(a, b, length)))
+    }
+
+    /// Spring resting at the current distance of the objects.
+    ///
+    /// # Errors
+    /// If either of the objects has been removed.
+    ///
+    /// # Panics
+    /// If ``a`` and ``b`` are the same object.
+    pub fn add_spring(
+        &mut self,
+        a: ObjectHandle,
+        b: ObjectHandle,
+        stiffness: f32,
+        damping: f32,
+    ) -> Result<LinkId, StaleHandle> {
+        let (a, b, length) = self.distance_between(a, b)?;
+        Ok(self.add_link(Link::spring(a, b, length, stiffness, damping)))
+    }
+
+    fn distance_between(
+        &self,
+        a: ObjectHandle,
+        b: ObjectHandle,
+    ) -> Result<(usize, usize, f32), StaleHandle> {
+        let a = self.handles.index_of(a)?;
+        let b = self.handles.index_of(b)?;
+        let distance = self.objects[a]
+            .get_center()
+            .metric_distance(&self.objects[b].get_center());
+        Ok((a, b, distance))
+    }
+
+    pub const fn links(&self) -> &Links {
+        &self.links
+    }
+
+    /// ``None`` if the link has already been removed, e.g. together with one of its
+    /// objects.
+    pub fn remove_link(&mut self, id: LinkId) -> Option<Link> {
+        self.links.remove(id)
+    }
+
+    pub const fn container(&self) -> &Container {
+        &self.container
+    }
+
+    /// Gives access to the container so it can be moved or resized at runtime.
+    pub const fn container_mut(&mut self) -> &mut Container {
+        &mut self.container
+    }
+
+    pub fn set_container(&mut self, container: Container) {
+        self.container = container;
+    }
+
+    pub const fn container_material(&self) -> ContactMaterial {
+        self.container_material
+    }
+
+    /// Sets restitution and friction used when objects hit the container.
+    pub const fn set_container_material(&mut self, material: ContactMaterial) {
+        self.container_material = material;
+    }
+
+    pub const fn objects_number(&self) -> usize {
         self.objects.len()
     }
 
-    pub fn solve_collisions_with_grid(&mut self) {
-        let mut grid: Grid<Vec<usize>> = Grid::new(GRID_ROWS as usize, GRID_COLS as usize);
+    pub fn objects(&self) -> &[VerletObject] {
+        &self.objects
+    }
 
-        for (idx, obj) in self.objects.iter().enumerate() {
-            let mut i = obj.get_center().x;
-            let mut j = obj.get_center().y;
+    pub fn objects_mut(&mut self) -> &mut [VerletObject] {
+        self.query_grid_outdated = true;
+        &mut self.objects
+    }
 
-            i += 1.0;
-            let i = (i / COL_WIDTH).trunc() as usize;
+    /// Point, area, nearest object and ray queries. The grid behind them is rebuilt only
+    /// when objects changed since the previous query.
+    pub fn query(&mut self) -> SpatialQuery<'_> {
+        if self.query_grid_outdated {
+            self.query_grid.rebuild(&self.objects);
+            self.query_grid_outdated = false;
+        }
+        SpatialQuery::new(&self.objects, &self.handles, &self.query_grid)
+    }
+
+    /// Replaces the algorithm used to find collision candidates.
+    pub fn set_broad_phase(&mut self, broad_phase: Box<dyn BroadPhase>) {
+        self.broad_phase = broad_phase;
+    }
+
+    /// Solves collisions on all cores. The parallel solver always uses a uniform grid and
+    /// ignores the configured broad phase. Off by default, since results then depend on
+    /// the number of threads.
+    pub fn set_parallel_collisions(&mut self, enabled: bool) {
+        self.parallel_solver = enabled.then(ParallelCollisionSolver::default);
+    }
 
-            j += 1.0;
-            let j = (j / ROW_HEIGHT).trunc() as usize;
+    pub const fn parallel_collisions(&self) -> bool {
+        self.parallel_solver.is_some()
+    }
 
-            grid[i][j].push(idx);
+    /// Records contacts of every step so they can be read through ``contacts`` and
+    /// ``contact_events``. Off by default.
+    pub fn set_contact_tracking(&mut self, enabled: bool) {
+        if enabled != self.contact_tracker.is_some() {
+            self.contact_tracker = enabled.then(ContactTracker::default);
         }
+    }
 
-        for row in 1..grid.rows() - 1 {
-            for col in 1..grid.cols() - 1 {
-                let start_row = row - 1;
-                let end_row = row + 1;
+    pub const fn contact_tracking(&self) -> bool {
+        self.contact_tracker.is_some()
+    }
 
-                let start_col = col - 1;
-                let end_col = col + 1;
+    /// Contacts of the last ``update``, empty when tracking is off.
+    pub fn contacts(&self) -> impl Iterator<Item = &Contact> {
+        self.contact_tracker
+            .iter()
+            .flat_map(ContactTracker::contacts)
+    }
 
-                let mut big_pocket = Vec::new();
+    /// Begin, persist and end events of the last ``update``, empty when tracking is off.
+    pub fn contact_events(&self) -> &[ContactEvent] {
+        self.contact_tracker
+            .as_ref()
+            .map_or(&[], ContactTracker::events)
+    }
 
-                for row in start_row..end_row {
-                    for col in start_col..end_col {
-                        big_pocket.append(&mut grid[row][col].clone());
-                    }
-                }
+    /// Called for every contact event at the end of ``update``, turns tracking on.
+    pub fn set_contact_listener(&mut self, listener: impl FnMut(&ContactEvent) + 'static) {
+        self.contact_tracker
+            .get_or_insert_with(ContactTracker::default)
+            .set_listener(Some(Box::new(listener)));
+    }
 
-                big_pocket.iter().tuple_combinations().for_each(|(&i, &j)| {
-                    if self.objects[i].collides_with(&self.objects[j]) {
-                        self.solve_collision(i, j);
-                    }
-                });
-            }
+    fn record_contacts(&mut self, dt: f32) {
+        let Some(tracker) = &mut self.contact_tracker else {
+            return;
+        };
+        for (i, j, penetration) in self.raw_contacts.drain(..) {
+            let (Some(a), Some(b)) = (self.handles.handle_at(i), self.handles.handle_at(j)) else {
+                continue;
+            };
+            tracker.record(a, b, &penetration, dt);
         }
     }
 
     pub fn solve_collisions(&mut self) {
-        (0..self.objects.len())
-            .tuple_combinations()
-            .for_each(|(i, j)| {
-                if self.objects[i].collides_with(&self.objects[j]) {
-                    self.solve_collision(i, j);
+        let recording = self.contact_tracker.is_some();
+        self.raw_contacts.clear();
+        if let Some(parallel_solver) = &mut self.parallel_solver {
+            parallel_solver.solve(
+                &mut self.objects,
+                recording.then_some(&mut self.raw_contacts),
+            );
+            return;
+        }
+
+        let mut pairs = std::mem::take(&mut self.collision_pairs);
+        pairs.clear();
+        self.broad_phase.find_pairs(&self.objects, &mut pairs);
+
+        for &(i, j) in &pairs {
+            if self.objects[i].collides_with(&self.objects[j]) {
+                if let Some(penetration) = self.solve_collision(i, j) {
+                    if recording {
+                        self.raw_contacts.push((i, j, penetration));
+                    }
                 }
-            });
+            }
+        }
+        self.collision_pairs = pairs;
     }
 
-    fn solve_collision(&mut self, obj1_idx: usize, obj2_idx: usize) {
-        let obj1 = &self.objects[obj1_idx];
-        let obj2 = &self.objects[obj2_idx];
+    fn solve_collision(&mut self, obj1_idx: usize, obj2_idx: usize) -> Option<Penetration> {
+        let (obj1, obj2) = pair_mut(&mut self.objects, obj1_idx, obj2_idx);
+        solve_collision(obj1, obj2)
+    }
 
-        let centers_distance = obj2.get_center().metric_distance(&obj1.get_center());
-        let radius_sum = obj2.get_radius() + obj1.get_radius();
+    fn resize_buffers(&mut self) {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.resize(self.objects.len());
+        }
+    }
 
-        if centers_distance < radius_sum {
-            let delta_versor = (obj2.get_center() - obj1.get_center()).normalize();
-            let m1 = PI * obj1.get_radius().powi(2);
-            let m2 = PI * obj2.get_radius().powi(2);
+    /// Position of the object between the start (``alpha == 0``) and the end
+    /// (``alpha == 1``) of the last tick, see ``SimulationClock::alpha``. Objects added
+    /// since then stay where they are.
+    ///
+    /// # Panics
+    /// If ``obj_idx`` is out of bounds.
+    #[must_use]
+    pub fn interpolated_center(&self, obj_idx: usize, alpha: f32) -> Vec2 {
+        self.tick_start[obj_idx].lerp(&self.objects[obj_idx].get_center(), alpha)
+    }
 
-            let adjustment_vector = delta_versor * (radius_sum - centers_distance);
+    /// ``alpha`` is the render interpolation factor, pass ``1.0`` to draw current positions.
+    pub fn update_vertex_buffer(&mut self, alpha: f32) {
+        let Some(renderer) = &mut self.renderer else {
+            return;
+        };
+        renderer.update_vertex_buffer(&self.objects, &self.tick_start, alpha);
+
+        let hulls: Vec<Shape> = self
+            .soft_bodies
+            .iter()
+            .map(|body| {
+                let vertices = body
+                    .members()
+                    .iter()
+                    .filter_map(|&member| self.handles.index_of(member).ok())
+                    .map(|obj_idx| {
+                        self.tick_start[obj_idx].lerp(&self.objects[obj_idx].get_center(), alpha)
+                    })
+                    .collect();
+                Shape::polygon(vertices, body.color)
+            })
+            .collect();
+        renderer.update_polygons(&hulls);
+    }
 
-            let adjustment1 = -(m2 / (m1 + m2)) * adjustment_vector;
-            let adjustment2 = (m1 / (m1 + m2)) * adjustment_vector;
+    /// Outlines every object with a ``width`` pixels wide ring of ``color``, zero turns it
+    /// off. Does nothing in a headless world.
+    pub const fn set_outline(&mut self, width: f32, color: [f32; 4]) {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_outline(width, color);
+        }
+    }
 
-            // let mut obj1 = &obj1;
-            self.objects[obj1_idx].shift(adjustment1);
-            self.objects[obj2_idx].shift(adjustment2);
+    pub fn render(&self) {
+        if let Some(renderer) = &self.renderer {
+            renderer.render();
         }
     }
+}
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    pub fn update_vertex_buffer(&mut self) {
-        self.renderer.update_vertex_buffer(&self.objects);
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec2;
+
+    use super::World;
+    use crate::engine2::{
+        objects_generator::ObjectsGenerator, soft_body::SoftBodyBuilder,
+        verlet_object::VerletObject,
+    };
+
+    fn populated() -> World {
+        let mut generator = ObjectsGenerator::with_seed(1);
+        generator.grid_columns = 4;
+        generator.grid_rows = 3;
+        let mut world = World::new();
+        world.add_obj_at(vec2(0.5, 0.5), 0.01);
+        world.populate(&mut generator);
+        world
     }
 
-    pub fn render(&self) {
-        self.renderer.render();
+    #[test]
+    fn soft_body_ids_survive_dropped_bodies() {
+        let mut world = World::new();
+        let first = world.add_soft_body(&SoftBodyBuilder::new(vec2(-0.4, 0.0), 0.2, 8));
+        let second = world.add_soft_body(&SoftBodyBuilder::new(vec2(0.4, 0.0), 0.2, 8));
+        let member = world.soft_body(first).unwrap().members()[0];
+        world.remove_object(member).unwrap();
+        world.update(1.0 / 60.0, 8);
+
+        assert!(world.soft_body(first).is_none());
+        world.soft_body_mut(second).unwrap().pressure = 0.0;
+        assert_eq!(world.remove_soft_body(second).unwrap().id(), second);
+        assert!(world.remove_soft_body(first).is_none());
     }
-}
 
-#[derive(Clone, Copy)]
-#[allow(dead_code)]
-enum Constraint {
-    Circular,
-    Rectangular,
+    #[test]
+    fn populated_objects_can_be_removed_before_the_first_tick() {
+        let mut world = populated();
+        let handle = world.handle_at(3).unwrap();
+        world.remove_object(handle).unwrap();
+        assert_eq!(world.objects().len(), 12);
+        for obj_idx in 0..world.objects().len() {
+            let center = world.objects()[obj_idx].get_center();
+            assert_eq!(world.interpolated_center(obj_idx, 0.0), center);
+        }
+    }
+
+    #[test]
+    fn populated_objects_are_interpolated_from_their_own_start() {
+        let mut world = populated();
+        world.update(1.0 / 60.0, 8);
+        let start: Vec<_> = world
+            .objects()
+            .iter()
+            .map(VerletObject::get_center)
+            .collect();
+        world.update(1.0 / 60.0, 8);
+
+        let handle = world.handle_at(0).unwrap();
+        world.remove_object(handle).unwrap();
+        // the last object moved into index 0
+        assert_eq!(world.interpolated_center(0, 0.0), start[12]);
+    }
 }
@@ -0,0 +1,3 @@
+#![warn(clippy::all, clippy::nursery, clippy::pedantic)]
+
+pub mod engine2;
@@ -1,27 +1,68 @@
 #![warn(clippy::all, clippy::nursery, clippy::pedantic)]
 
+// kept as it is, newer lints flag it
+#[allow(
+    dead_code,
+    clippy::missing_const_for_fn,
+    clippy::manual_is_multiple_of,
+    clippy::needless_pass_by_ref_mut
+)]
 mod bench;
-mod engine2;
 
 use std::time::Instant;
 
-use glium::glutin::event_loop::{ControlFlow, EventLoop};
+use glium::glutin::{
+    event::VirtualKeyCode,
+    event_loop::{ControlFlow, EventLoop},
+};
 use nalgebra_glm::vec2;
 use winit_input_helper::WinitInputHelper;
 
-use crate::{
-    bench::Bench,
-    engine2::{objects_generator::ObjectsGenerator, world},
+use engine_2d::engine2::{
+    clock::SimulationClock,
+    objects_generator::ObjectsGenerator,
+    radius_distribution::RadiusStats,
+    raster::{ImageFormat, SoftwareRenderer},
+    replay::{Action, Recorder, Recording},
+    snapshot::SnapshotFormat,
+    verlet_object::VerletObject,
+    world::World,
 };
 
+use crate::bench::Bench;
+
+const ERASER_RADIUS: f32 = 0.02;
+const SNAPSHOT_PATH: &str = "world.snapshot";
+const SCREENSHOT_SIZE: u32 = 1000;
+
 fn main() {
+    let options = Options::from_args();
+    if let Some(path) = &options.replay {
+        replay(path, options.screenshot.as_deref());
+        return;
+    }
+
     let event_loop = EventLoop::new();
-    let mut world = world::World::new(&event_loop);
-    let mut generator = ObjectsGenerator::default();
+    let mut world = World::with_renderer(&event_loop);
+    // the parallel solver isn't reproducible, recordings need the sequential one
+    world.set_parallel_collisions(options.record.is_none());
+    let mut generator = options
+        .seed
+        .map_or_else(ObjectsGenerator::default, ObjectsGenerator::with_seed);
+    println!("Seed: {}", generator.seed());
     world.populate(&mut generator);
+    if let Some(stats) =
+        RadiusStats::from_radii(world.objects().iter().map(VerletObject::get_radius))
+    {
+        println!("Generated {stats}");
+    }
 
     let mut input = WinitInputHelper::new();
     let mut timer = Timer::new();
+    let mut clock = SimulationClock::default();
+    let mut recorder = options.record.as_ref().map(|_| {
+        Recorder::start(&world, &clock).expect("The default world can always be recorded.")
+    });
 
     // TODO: Add this functionality to Timer:
     let mut mouse_timer: Instant = Instant::now();
@@ -35,38 +76,65 @@ fn main() {
         control_flow.set_poll();
         bench.loop_started();
 
-        #[allow(clippy::collapsible_if)]
+        #[allow(clippy::collapsible_if, clippy::while_float)]
         if input.update(&event) {
             if input.quit() {
                 *control_flow = ControlFlow::Exit;
+                if let (Some(recorder), Some(path)) = (recorder.take(), &options.record) {
+                    save_recording(&recorder.finish(&clock), path, &world);
+                }
             }
+            snapshot_keys(&input, &mut world, recorder.is_some());
             if let Some((x, y)) = input.mouse() {
                 if input.mouse_pressed(0) {
                     mouse_timer = Instant::now();
-                    world.add_obj_at(world.to_gl_coords(vec2(x, y)), generator.random_radius());
+                    let center = generator.jittered(world.to_gl_coords(vec2(x, y)));
+                    let radius = generator.random_radius();
+                    perform(
+                        &mut world,
+                        &mut recorder,
+                        &clock,
+                        Action::Spawn { center, radius },
+                    );
                 }
                 if input.mouse_held(0) {
                     mouse_tick_delta += mouse_timer.elapsed().as_secs_f32();
                     mouse_timer = Instant::now();
                     while mouse_tick_delta > mouse_tick_every {
-                        world.add_obj_at(world.to_gl_coords(vec2(x, y)), generator.random_radius());
+                        let center = generator.jittered(world.to_gl_coords(vec2(x, y)));
+                        let radius = generator.random_radius();
+                        perform(
+                            &mut world,
+                            &mut recorder,
+                            &clock,
+                            Action::Spawn { center, radius },
+                        );
                         mouse_tick_delta -= mouse_tick_every;
                     }
                 }
+                if input.mouse_held(1) {
+                    let center = world.to_gl_coords(vec2(x, y));
+                    let radius = ERASER_RADIUS;
+                    perform(
+                        &mut world,
+                        &mut recorder,
+                        &clock,
+                        Action::Erase { center, radius },
+                    );
+                }
             }
             bench.events_cleared();
 
             // world.update_positions(timer.dt32());
             // bench.positions_updated();
 
-            // // world.solve_collisions_with_grid();
             // world.solve_collisions();
             // bench.collisions_solved();
 
-            world.update(timer.dt32(), 4);
+            clock.advance(&mut world, timer.dt32());
             bench.collisions_solved();
 
-            world.update_vertex_buffer();
+            world.update_vertex_buffer(clock.alpha());
             bench.vb_updated();
 
             world.render();
@@ -84,6 +152,105 @@ fn main() {
     });
 }
 
+/// F5 saves the world, F9 loads it back.
+fn snapshot_keys(input: &WinitInputHelper, world: &mut World, recording: bool) {
+    if input.key_pressed(VirtualKeyCode::F5) {
+        match world.save(SNAPSHOT_PATH, SnapshotFormat::Binary) {
+            Ok(()) => println!("World saved to {SNAPSHOT_PATH}."),
+            Err(error) => println!("Saving failed: {error}"),
+        }
+    }
+    if input.key_pressed(VirtualKeyCode::F9) {
+        if recording {
+            println!("Loading snapshots is disabled while recording.");
+        } else {
+            match world.load(SNAPSHOT_PATH) {
+                Ok(_) => println!("World loaded from {SNAPSHOT_PATH}."),
+                Err(error) => println!("Loading failed: {error}"),
+            }
+        }
+    }
+}
+
+fn replay(path: &str, screenshot: Option<&str>) {
+    let recording = Recording::load(path).expect("Unable to load the recording.");
+    let world = recording.replay();
+    println!(
+        "Replayed {} ticks, {} objects, final state hash: {:016x}",
+        recording.ticks,
+        world.objects_number(),
+        world.state_hash()
+    );
+    if let Some(screenshot) = screenshot {
+        let format = ImageFormat::from_path(screenshot)
+            .expect("Screenshots have to be saved as .png or .ppm.");
+        let image = SoftwareRenderer::new(SCREENSHOT_SIZE, SCREENSHOT_SIZE).render(&world);
+        match image.save(screenshot, format) {
+            Ok(()) => println!("Screenshot saved to {screenshot}"),
+            Err(error) => println!("Saving the screenshot failed: {error}"),
+        }
+    }
+}
+
+fn save_recording(recording: &Recording, path: &str, world: &World) {
+    match recording.save(path) {
+        Ok(()) => println!(
+            "Recording saved to {path}, final state hash: {:016x}",
+            world.state_hash()
+        ),
+        Err(error) => println!("Saving the recording failed: {error}"),
+    }
+}
+
+/// Applies the action, logging it when a recording is running.
+fn perform(
+    world: &mut World,
+    recorder: &mut Option<Recorder>,
+    clock: &SimulationClock,
+    action: Action,
+) {
+    match recorder {
+        Some(recorder) => recorder.apply(world, clock, action),
+        None => action.apply(world),
+    }
+}
+
+/// ``--seed <u64>``, ``--record <file>`` and ``--replay <file>`` command line options,
+/// ``--screenshot <file.png|file.ppm>`` saves the last frame of a replay.
+#[derive(Default)]
+struct Options {
+    seed: Option<u64>,
+    record: Option<String>,
+    replay: Option<String>,
+    screenshot: Option<String>,
+}
+impl Options {
+    fn from_args() -> Self {
+        let mut options = Self::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .unwrap_or_else(|| panic!("{arg} needs a value."))
+            };
+            match arg.as_str() {
+                "--seed" => {
+                    options.seed = Some(
+                        value()
+                            .parse()
+                            .expect("Seed has to be an unsigned integer."),
+                    );
+                }
+                "--record" => options.record = Some(value()),
+                "--replay" => options.replay = Some(value()),
+                "--screenshot" => options.screenshot = Some(value()),
+                _ => panic!("Unknown option {arg}."),
+            }
+        }
+        options
+    }
+}
+
 struct Timer {
     last_instant: Instant,
 }